@@ -0,0 +1,633 @@
+use crate::utils::{section_name, SectionInfo};
+use anyhow::{anyhow, Result};
+use petgraph::algo::dominators;
+use petgraph::graph::{Graph, NodeIndex};
+use serde::Serialize;
+use std::collections::HashMap;
+use wasmparser::{
+    ElementItems, ExternalKind, KnownCustom, Name, Operator, OperatorsReader, Payload, TypeRef,
+};
+use wit_parser::{
+    Function, FunctionKind, Handle, Resolve, Results, Type, TypeDefKind, TypeId, WorldItem,
+    WorldKey,
+};
+
+/// The WIT world implemented by a component, flattened for display.
+#[derive(Debug, Clone, Serialize)]
+pub struct WorldInfo {
+    pub name: String,
+    pub imports: Vec<WorldEntry>,
+    pub exports: Vec<WorldEntry>,
+}
+
+/// An imported or exported item of a world: an interface, a function or a type.
+#[derive(Debug, Clone, Serialize)]
+pub struct WorldEntry {
+    pub name: String,
+    pub kind: String,
+    pub functions: Vec<FunctionInfo>,
+    pub types: Vec<TypeInfo>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FunctionInfo {
+    pub name: String,
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TypeInfo {
+    pub name: String,
+    pub definition: String,
+    /// Constructor, methods and static functions when the type is a resource
+    pub functions: Vec<FunctionInfo>,
+}
+
+/// A core module embedded in a component.
+#[derive(Debug, Clone, Serialize)]
+pub struct CoreModuleInfo {
+    pub index: usize,
+    pub offset: usize,
+    pub size: usize,
+}
+
+/// Size breakdown of a core module, in the spirit of `twiggy top` and
+/// `twiggy dominators`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleProfile {
+    /// Index of the module inside its component, `None` for a plain core module
+    pub module: Option<usize>,
+    pub size: usize,
+    /// Every section of the module; custom sections are named `custom:<name>`
+    pub sections: Vec<SectionInfo>,
+    pub function_count: usize,
+    /// Largest defined functions, by body size
+    pub functions: Vec<FunctionSize>,
+    pub data_segment_count: usize,
+    /// Largest data segments, by payload size
+    pub data_segments: Vec<DataSegmentSize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FunctionSize {
+    pub index: u32,
+    pub name: String,
+    /// Bytes of the function body itself
+    pub size: usize,
+    /// Bytes that would go away with this function: its body plus every
+    /// function only reachable through it
+    pub retained_size: usize,
+    /// Immediate dominator in the call graph: the function (or export, start
+    /// function or table) keeping this one alive. `None` when unreachable.
+    pub retained_by: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DataSegmentSize {
+    pub index: usize,
+    pub kind: String,
+    pub size: usize,
+}
+
+/// Decodes the WIT world of a component, or the world embedded in a core
+/// module's `component-type` custom section.
+///
+/// Returns `None` for core modules without component metadata.
+pub fn decode_world(wasm_bytes: &[u8]) -> Result<Option<WorldInfo>> {
+    if wasmparser::Parser::is_component(wasm_bytes) {
+        return match wit_component::decode(wasm_bytes)
+            .map_err(|e| anyhow!("Failed to decode component: {:#}", e))?
+        {
+            wit_component::DecodedWasm::Component(resolve, world) => {
+                Ok(Some(describe_world(&resolve, world)))
+            }
+            wit_component::DecodedWasm::WitPackages(..) => Ok(None),
+        };
+    }
+
+    let (_, bindgen) = wit_component::metadata::decode(wasm_bytes)
+        .map_err(|e| anyhow!("Failed to decode component metadata: {:#}", e))?;
+    let world = &bindgen.resolve.worlds[bindgen.world];
+    if world.imports.is_empty() && world.exports.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(describe_world(&bindgen.resolve, bindgen.world)))
+}
+
+/// Lists the core modules nested anywhere inside a component.
+pub fn nested_modules(wasm_bytes: &[u8]) -> Result<Vec<CoreModuleInfo>> {
+    let mut modules = Vec::new();
+
+    for payload in wasmparser::Parser::new(0).parse_all(wasm_bytes) {
+        if let wasmparser::Payload::ModuleSection {
+            unchecked_range: range,
+            ..
+        } = payload?
+        {
+            modules.push(CoreModuleInfo {
+                index: modules.len(),
+                offset: range.start,
+                size: range.len(),
+            });
+        }
+    }
+
+    Ok(modules)
+}
+
+/// Profiles a core module, or every core module nested in a component.
+///
+/// Only the `top` largest functions and data segments are kept.
+pub fn profile_modules(wasm_bytes: &[u8], top: usize) -> Result<Vec<ModuleProfile>> {
+    if !wasmparser::Parser::is_component(wasm_bytes) {
+        return Ok(vec![profile_module(wasm_bytes, None, top)?]);
+    }
+
+    nested_modules(wasm_bytes)?
+        .iter()
+        .map(|module| {
+            let bytes = &wasm_bytes[module.offset..module.offset + module.size];
+            profile_module(bytes, Some(module.index), top)
+        })
+        .collect()
+}
+
+fn profile_module(wasm_bytes: &[u8], module: Option<usize>, top: usize) -> Result<ModuleProfile> {
+    let mut sections = Vec::new();
+    let mut imports = Vec::new();
+    let mut names = HashMap::new();
+    // Functions referenced from outside the call graph, with the reason why
+    let mut roots: Vec<(u32, String)> = Vec::new();
+    // Body size and callees of each defined function
+    let mut bodies: Vec<(usize, Vec<u32>)> = Vec::new();
+    let mut data_segments = Vec::new();
+
+    for payload in wasmparser::Parser::new(0).parse_all(wasm_bytes) {
+        let payload = payload?;
+
+        if let Some((_, range)) = payload.as_section() {
+            let name = match &payload {
+                Payload::CustomSection(reader) => Some(format!("custom:{}", reader.name())),
+                _ => section_name(&payload).map(str::to_string),
+            };
+            if let Some(name) = name {
+                sections.push(SectionInfo {
+                    name,
+                    offset: range.start,
+                    size: range.len(),
+                });
+            }
+        }
+
+        match payload {
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    let import = import?;
+                    if let TypeRef::Func(_) = import.ty {
+                        imports.push(format!("import {}::{}", import.module, import.name));
+                    }
+                }
+            }
+            Payload::ExportSection(reader) => {
+                for export in reader {
+                    let export = export?;
+                    if export.kind == ExternalKind::Func {
+                        roots.push((export.index, format!("export '{}'", export.name)));
+                    }
+                }
+            }
+            Payload::StartSection { func, .. } => roots.push((func, "start".to_string())),
+            Payload::ElementSection(reader) => {
+                for element in reader {
+                    match element?.items {
+                        ElementItems::Functions(functions) => {
+                            for function in functions {
+                                roots.push((function?, "table".to_string()));
+                            }
+                        }
+                        ElementItems::Expressions(_, exprs) => {
+                            for expr in exprs {
+                                for function in referenced_functions(expr?.get_operators_reader())?
+                                {
+                                    roots.push((function, "table".to_string()));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            Payload::GlobalSection(reader) => {
+                for global in reader {
+                    let init = global?.init_expr.get_operators_reader();
+                    for function in referenced_functions(init)? {
+                        roots.push((function, "global".to_string()));
+                    }
+                }
+            }
+            Payload::CodeSectionEntry(body) => {
+                let callees = referenced_functions(body.get_operators_reader()?)?;
+                bodies.push((body.range().len(), callees));
+            }
+            Payload::DataSection(reader) => {
+                for (index, data) in reader.into_iter().enumerate() {
+                    let data = data?;
+                    let kind = match data.kind {
+                        wasmparser::DataKind::Active { .. } => "active",
+                        wasmparser::DataKind::Passive => "passive",
+                    };
+                    data_segments.push(DataSegmentSize {
+                        index,
+                        kind: kind.to_string(),
+                        size: data.data.len(),
+                    });
+                }
+            }
+            Payload::CustomSection(reader) => {
+                let KnownCustom::Name(subsections) = reader.as_known() else {
+                    continue;
+                };
+                // Names are a nicety; a malformed section just leaves functions unnamed
+                for subsection in subsections {
+                    let Ok(Name::Function(map)) = subsection else {
+                        continue;
+                    };
+                    for naming in map.into_iter().flatten() {
+                        names.insert(naming.index, naming.name.to_string());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let imported = imports.len();
+    let function_name = |index: usize| match names.get(&(index as u32)) {
+        Some(name) => name.clone(),
+        None if index < imported => imports[index].clone(),
+        None => format!("func[{index}]"),
+    };
+
+    // Call graph with a synthetic root standing for everything the host can reach
+    let mut graph = Graph::<(), ()>::new();
+    let root = graph.add_node(());
+    let nodes: Vec<NodeIndex> = (0..imported + bodies.len())
+        .map(|_| graph.add_node(()))
+        .collect();
+    for (function, _) in &roots {
+        if let Some(&node) = nodes.get(*function as usize) {
+            graph.add_edge(root, node, ());
+        }
+    }
+    for (index, (_, callees)) in bodies.iter().enumerate() {
+        for callee in callees {
+            if let Some(&node) = nodes.get(*callee as usize) {
+                graph.add_edge(nodes[imported + index], node, ());
+            }
+        }
+    }
+
+    let dominators = dominators::simple_fast(&graph, root);
+    let function_of = |node: NodeIndex| node.index() - 1;
+    let shallow = |function: usize| function.checked_sub(imported).map_or(0, |i| bodies[i].0);
+
+    // Accumulate sizes bottom-up through the dominator tree
+    let mut children: HashMap<NodeIndex, Vec<NodeIndex>> = HashMap::new();
+    for &node in &nodes {
+        if let Some(idom) = dominators.immediate_dominator(node) {
+            children.entry(idom).or_default().push(node);
+        }
+    }
+    let mut retained: Vec<usize> = (0..nodes.len()).map(shallow).collect();
+    let mut order = Vec::new();
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        order.push(node);
+        stack.extend(children.get(&node).into_iter().flatten());
+    }
+    for &node in order.iter().rev() {
+        if node == root {
+            continue;
+        }
+        if let Some(idom) = dominators
+            .immediate_dominator(node)
+            .filter(|&idom| idom != root)
+        {
+            retained[function_of(idom)] += retained[function_of(node)];
+        }
+    }
+
+    let retained_by = |function: usize| {
+        let idom = dominators.immediate_dominator(nodes[function])?;
+        if idom != root {
+            return Some(function_name(function_of(idom)));
+        }
+        let reason = roots
+            .iter()
+            .find(|(root, _)| *root as usize == function)
+            .map(|(_, reason)| reason.clone());
+        Some(reason.unwrap_or_else(|| "several roots".to_string()))
+    };
+
+    let mut functions: Vec<FunctionSize> = (imported..nodes.len())
+        .map(|function| FunctionSize {
+            index: function as u32,
+            name: function_name(function),
+            size: shallow(function),
+            retained_size: retained[function],
+            retained_by: retained_by(function),
+        })
+        .collect();
+    functions.sort_by(|a, b| b.size.cmp(&a.size).then(a.index.cmp(&b.index)));
+    functions.truncate(top);
+
+    let data_segment_count = data_segments.len();
+    data_segments.sort_by(|a, b| b.size.cmp(&a.size).then(a.index.cmp(&b.index)));
+    data_segments.truncate(top);
+
+    Ok(ModuleProfile {
+        module,
+        size: wasm_bytes.len(),
+        sections,
+        function_count: bodies.len(),
+        functions,
+        data_segment_count,
+        data_segments,
+    })
+}
+
+/// Functions named by `call`, `return_call` and `ref.func` instructions.
+fn referenced_functions(mut reader: OperatorsReader) -> Result<Vec<u32>> {
+    let mut functions = Vec::new();
+
+    while !reader.eof() {
+        match reader.read()? {
+            Operator::Call { function_index }
+            | Operator::ReturnCall { function_index }
+            | Operator::RefFunc { function_index } => functions.push(function_index),
+            _ => {}
+        }
+    }
+
+    Ok(functions)
+}
+
+fn describe_world(resolve: &Resolve, world_id: wit_parser::WorldId) -> WorldInfo {
+    let world = &resolve.worlds[world_id];
+    let name = match world.package {
+        Some(package) => format!("{}/{}", resolve.packages[package].name, world.name),
+        None => world.name.clone(),
+    };
+
+    WorldInfo {
+        name,
+        imports: world
+            .imports
+            .iter()
+            .map(|(key, item)| describe_item(resolve, key, item))
+            .collect(),
+        exports: world
+            .exports
+            .iter()
+            .map(|(key, item)| describe_item(resolve, key, item))
+            .collect(),
+    }
+}
+
+fn describe_item(resolve: &Resolve, key: &WorldKey, item: &WorldItem) -> WorldEntry {
+    let name = resolve.name_world_key(key);
+
+    match item {
+        WorldItem::Interface { id, .. } => {
+            let interface = &resolve.interfaces[*id];
+            WorldEntry {
+                name,
+                kind: "interface".to_string(),
+                functions: interface
+                    .functions
+                    .values()
+                    .filter(|f| matches!(f.kind, FunctionKind::Freestanding))
+                    .map(|f| describe_function(resolve, f))
+                    .collect(),
+                types: interface
+                    .types
+                    .values()
+                    .map(|ty| describe_type(resolve, *ty, interface.functions.values()))
+                    .collect(),
+            }
+        }
+        WorldItem::Function(function) => WorldEntry {
+            name,
+            kind: "function".to_string(),
+            functions: vec![describe_function(resolve, function)],
+            types: Vec::new(),
+        },
+        WorldItem::Type(ty) => WorldEntry {
+            name,
+            kind: "type".to_string(),
+            functions: Vec::new(),
+            types: vec![describe_type(resolve, *ty, std::iter::empty())],
+        },
+    }
+}
+
+fn describe_function(resolve: &Resolve, function: &Function) -> FunctionInfo {
+    // Methods take `self` implicitly in WIT syntax
+    let skip = usize::from(matches!(function.kind, FunctionKind::Method(_)));
+    let params = function
+        .params
+        .iter()
+        .skip(skip)
+        .map(|(name, ty)| format!("{}: {}", name, type_name(resolve, ty)))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let (name, signature) = match function.kind {
+        FunctionKind::Constructor(_) => {
+            ("constructor".to_string(), format!("constructor({params})"))
+        }
+        FunctionKind::Freestanding => (
+            function.name.clone(),
+            format!("func({params}){}", results(resolve, &function.results)),
+        ),
+        FunctionKind::Method(_) | FunctionKind::Static(_) => {
+            let name = function.item_name().to_string();
+            let prefix = if matches!(function.kind, FunctionKind::Static(_)) {
+                "static "
+            } else {
+                ""
+            };
+            (
+                name,
+                format!(
+                    "{prefix}func({params}){}",
+                    results(resolve, &function.results)
+                ),
+            )
+        }
+    };
+
+    FunctionInfo { name, signature }
+}
+
+fn results(resolve: &Resolve, results: &Results) -> String {
+    match results {
+        Results::Anon(ty) => format!(" -> {}", type_name(resolve, ty)),
+        Results::Named(named) if named.is_empty() => String::new(),
+        Results::Named(named) => format!(
+            " -> ({})",
+            named
+                .iter()
+                .map(|(name, ty)| format!("{}: {}", name, type_name(resolve, ty)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+fn describe_type<'a>(
+    resolve: &Resolve,
+    id: TypeId,
+    functions: impl Iterator<Item = &'a Function>,
+) -> TypeInfo {
+    let def = &resolve.types[id];
+    let name = def.name.clone().unwrap_or_default();
+
+    let definition = match &def.kind {
+        TypeDefKind::Record(record) => format!(
+            "record {name} {{ {} }}",
+            record
+                .fields
+                .iter()
+                .map(|f| format!("{}: {}", f.name, type_name(resolve, &f.ty)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        TypeDefKind::Resource => format!("resource {name}"),
+        TypeDefKind::Flags(flags) => format!(
+            "flags {name} {{ {} }}",
+            flags
+                .flags
+                .iter()
+                .map(|f| f.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        TypeDefKind::Variant(variant) => format!(
+            "variant {name} {{ {} }}",
+            variant
+                .cases
+                .iter()
+                .map(|c| match &c.ty {
+                    Some(ty) => format!("{}({})", c.name, type_name(resolve, ty)),
+                    None => c.name.clone(),
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        TypeDefKind::Enum(enum_) => format!(
+            "enum {name} {{ {} }}",
+            enum_
+                .cases
+                .iter()
+                .map(|c| c.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        TypeDefKind::Type(ty) => format!("type {name} = {}", type_name(resolve, ty)),
+        _ => format!("type {name} = {}", anonymous_type(resolve, &def.kind)),
+    };
+
+    let functions = match def.kind {
+        TypeDefKind::Resource => functions
+            .filter(|f| match f.kind {
+                FunctionKind::Method(owner)
+                | FunctionKind::Static(owner)
+                | FunctionKind::Constructor(owner) => owner == id,
+                FunctionKind::Freestanding => false,
+            })
+            .map(|f| describe_function(resolve, f))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    TypeInfo {
+        name,
+        definition,
+        functions,
+    }
+}
+
+/// Renders a type reference in WIT syntax.
+fn type_name(resolve: &Resolve, ty: &Type) -> String {
+    match ty {
+        Type::Bool => "bool".to_string(),
+        Type::U8 => "u8".to_string(),
+        Type::U16 => "u16".to_string(),
+        Type::U32 => "u32".to_string(),
+        Type::U64 => "u64".to_string(),
+        Type::S8 => "s8".to_string(),
+        Type::S16 => "s16".to_string(),
+        Type::S32 => "s32".to_string(),
+        Type::S64 => "s64".to_string(),
+        Type::F32 => "f32".to_string(),
+        Type::F64 => "f64".to_string(),
+        Type::Char => "char".to_string(),
+        Type::String => "string".to_string(),
+        Type::Id(id) => {
+            let def = &resolve.types[*id];
+            match &def.name {
+                Some(name) => name.clone(),
+                None => anonymous_type(resolve, &def.kind),
+            }
+        }
+    }
+}
+
+fn anonymous_type(resolve: &Resolve, kind: &TypeDefKind) -> String {
+    let optional = |ty: &Option<Type>| match ty {
+        Some(ty) => type_name(resolve, ty),
+        None => "_".to_string(),
+    };
+
+    match kind {
+        TypeDefKind::List(ty) => format!("list<{}>", type_name(resolve, ty)),
+        TypeDefKind::Option(ty) => format!("option<{}>", type_name(resolve, ty)),
+        TypeDefKind::Result(result) => match (&result.ok, &result.err) {
+            (None, None) => "result".to_string(),
+            (Some(ok), None) => format!("result<{}>", type_name(resolve, ok)),
+            (ok, Some(err)) => format!("result<{}, {}>", optional(ok), type_name(resolve, err)),
+        },
+        TypeDefKind::Tuple(tuple) => format!(
+            "tuple<{}>",
+            tuple
+                .types
+                .iter()
+                .map(|ty| type_name(resolve, ty))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        TypeDefKind::Handle(Handle::Own(id)) => type_name(resolve, &Type::Id(*id)),
+        TypeDefKind::Handle(Handle::Borrow(id)) => {
+            format!("borrow<{}>", type_name(resolve, &Type::Id(*id)))
+        }
+        TypeDefKind::Future(ty) => match ty {
+            Some(ty) => format!("future<{}>", type_name(resolve, ty)),
+            None => "future".to_string(),
+        },
+        TypeDefKind::Stream(stream) => {
+            format!(
+                "stream<{}, {}>",
+                optional(&stream.element),
+                optional(&stream.end)
+            )
+        }
+        TypeDefKind::Type(ty) => type_name(resolve, ty),
+        TypeDefKind::Record(_)
+        | TypeDefKind::Resource
+        | TypeDefKind::Flags(_)
+        | TypeDefKind::Variant(_)
+        | TypeDefKind::Enum(_)
+        | TypeDefKind::Unknown => "<anonymous>".to_string(),
+    }
+}
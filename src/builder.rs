@@ -0,0 +1,420 @@
+use crate::config::{BuildProfile, BuildSection, ProjectConfig};
+use crate::utils::{
+    build_output_dirs, is_derived_artifact, is_wasm_file, run_command_with, RunOptions,
+};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Runs a project's toolchain and locates the `.wasm` files it produced.
+#[async_trait]
+pub trait Builder: Send + Sync {
+    /// Tool name shown in progress messages
+    fn name(&self) -> &'static str;
+
+    /// Builds the project and returns the `.wasm` files the build produced.
+    async fn build(
+        &self,
+        project_root: &Path,
+        config: &ProjectConfig,
+        profile: &BuildProfile,
+        options: &RunOptions,
+    ) -> Result<Vec<PathBuf>>;
+
+    /// Whether the outputs are core modules that `output_format = "component"`
+    /// wraps into components.
+    fn produces_core_modules(&self, config: &BuildSection) -> bool;
+
+    /// Commands printing the toolchain versions, which decide the outputs
+    /// as much as the sources do.
+    fn version_commands(&self) -> &'static [(&'static str, &'static [&'static str])] {
+        &[]
+    }
+
+    /// Rust target the toolchain compiles for, which rustup must have
+    /// installed; `None` for toolchains that are not rustup-based.
+    fn rust_target<'a>(&self, _config: &'a BuildSection) -> Option<&'a str> {
+        None
+    }
+}
+
+/// Picks the builder for the project at `project_root`: `build.command`,
+/// else `build.tool`, else the one matching the project's manifest.
+pub fn for_project(project_root: &Path, config: &BuildSection) -> Result<Box<dyn Builder>> {
+    if let Some(command) = &config.command {
+        return Ok(Box::new(CustomBuilder {
+            command: command.clone(),
+        }));
+    }
+
+    let tool = match &config.tool {
+        Some(tool) => tool.as_str(),
+        None if project_root.join("Cargo.toml").exists() => "cargo",
+        None if project_root.join("package.json").exists() => "npm",
+        None => {
+            return Err(anyhow!(
+                "Unsupported project type: no Cargo.toml or package.json. Set `build.tool` or `build.command` in wasm-wizard.toml"
+            ))
+        }
+    };
+
+    Ok(match tool {
+        "cargo" => Box::new(CargoBuilder),
+        "cargo-component" => Box::new(CargoComponentBuilder),
+        "wasm-pack" => Box::new(WasmPackBuilder),
+        "npm" => Box::new(NpmBuilder),
+        other => return Err(anyhow!("Unsupported build tool \"{}\"", other)),
+    })
+}
+
+/// Environment describing the build to `build.command` and `[hooks]`
+/// commands; `artifact` is the module a post-build or post-optimize hook
+/// runs for.
+pub fn build_env(
+    project_root: &Path,
+    config: &ProjectConfig,
+    profile: &BuildProfile,
+    artifact: Option<&Path>,
+) -> Vec<(&'static str, String)> {
+    let mut env = vec![
+        (
+            "WASM_WIZARD_PROJECT_ROOT",
+            project_root.display().to_string(),
+        ),
+        ("WASM_WIZARD_PROFILE", profile.name.clone()),
+        ("WASM_WIZARD_CARGO_PROFILE", profile.cargo_profile.clone()),
+        ("WASM_WIZARD_TARGET", config.build.target.clone()),
+    ];
+    if let Some(artifact) = artifact {
+        env.push(("WASM_WIZARD_ARTIFACT", artifact.display().to_string()));
+    }
+    env
+}
+
+const RUST_VERSIONS: &[(&str, &[&str])] = &[("rustc", &["-vV"]), ("cargo", &["-V"])];
+
+/// `cargo build`, which reports the files it produced on stdout.
+struct CargoBuilder;
+
+#[async_trait]
+impl Builder for CargoBuilder {
+    fn name(&self) -> &'static str {
+        "cargo"
+    }
+
+    async fn build(
+        &self,
+        project_root: &Path,
+        config: &ProjectConfig,
+        profile: &BuildProfile,
+        options: &RunOptions,
+    ) -> Result<Vec<PathBuf>> {
+        let features = profile.features.join(",");
+        let mut args = vec![
+            "build",
+            "--message-format=json-render-diagnostics",
+            "--profile",
+            profile.cargo_profile.as_str(),
+            "--target",
+            config.build.target.as_str(),
+        ];
+        if !features.is_empty() {
+            args.extend(["--features", features.as_str()]);
+        }
+
+        // Diagnostics are rendered to stderr; stdout only carries JSON
+        let options = RunOptions {
+            quiet_stdout: true,
+            ..options.clone()
+        };
+        let output = run_command_with("cargo", &args, Some(project_root), &options).await?;
+        expect_outputs(
+            cargo_wasm_artifacts(&output.stdout),
+            "Rust crates need a `cdylib` or `bin` target.",
+        )
+    }
+
+    /// Cargo produces core modules except for `wasm32-wasip2`, which links
+    /// components itself.
+    fn produces_core_modules(&self, config: &BuildSection) -> bool {
+        config.target != "wasm32-wasip2"
+    }
+
+    fn version_commands(&self) -> &'static [(&'static str, &'static [&'static str])] {
+        RUST_VERSIONS
+    }
+
+    fn rust_target<'a>(&self, config: &'a BuildSection) -> Option<&'a str> {
+        Some(&config.target)
+    }
+}
+
+/// `cargo component build`, which reports the components it produced on
+/// stdout like cargo.
+struct CargoComponentBuilder;
+
+#[async_trait]
+impl Builder for CargoComponentBuilder {
+    fn name(&self) -> &'static str {
+        "cargo-component"
+    }
+
+    async fn build(
+        &self,
+        project_root: &Path,
+        config: &ProjectConfig,
+        profile: &BuildProfile,
+        options: &RunOptions,
+    ) -> Result<Vec<PathBuf>> {
+        let features = profile.features.join(",");
+        let mut args = vec![
+            "component",
+            "build",
+            "--message-format=json-render-diagnostics",
+            "--target",
+            config.build.target.as_str(),
+        ];
+        match profile.cargo_profile.as_str() {
+            "dev" => {}
+            "release" => args.push("--release"),
+            other => args.extend(["--profile", other]),
+        }
+        if !features.is_empty() {
+            args.extend(["--features", features.as_str()]);
+        }
+
+        // cargo-component forwards cargo's messages, which list the files it
+        // componentizes in place, including those of up to date builds
+        let options = RunOptions {
+            quiet_stdout: true,
+            ..options.clone()
+        };
+        let output = run_command_with("cargo", &args, Some(project_root), &options).await?;
+        expect_outputs(
+            cargo_wasm_artifacts(&output.stdout),
+            "Rust crates need a `cdylib` or `bin` target.",
+        )
+    }
+
+    fn produces_core_modules(&self, _config: &BuildSection) -> bool {
+        false
+    }
+
+    fn version_commands(&self) -> &'static [(&'static str, &'static [&'static str])] {
+        &[
+            ("rustc", &["-vV"]),
+            ("cargo", &["-V"]),
+            ("cargo", &["component", "--version"]),
+        ]
+    }
+
+    fn rust_target<'a>(&self, config: &'a BuildSection) -> Option<&'a str> {
+        Some(&config.target)
+    }
+}
+
+/// `wasm-pack build`, which emits wasm-bindgen modules with JavaScript glue
+/// into `pkg/`.
+struct WasmPackBuilder;
+
+#[async_trait]
+impl Builder for WasmPackBuilder {
+    fn name(&self) -> &'static str {
+        "wasm-pack"
+    }
+
+    async fn build(
+        &self,
+        project_root: &Path,
+        config: &ProjectConfig,
+        profile: &BuildProfile,
+        options: &RunOptions,
+    ) -> Result<Vec<PathBuf>> {
+        let mode = match profile.cargo_profile.as_str() {
+            "dev" => "--dev",
+            "release" => "--release",
+            other => {
+                return Err(anyhow!(
+                    "wasm-pack only builds the dev and release cargo profiles, not \"{}\"",
+                    other
+                ))
+            }
+        };
+
+        let features = profile.features.join(",");
+        let mut args = vec!["build", mode, "--target", "web", "--out-dir", "pkg"];
+        if !features.is_empty() {
+            // Arguments after `--` go to cargo
+            args.extend(["--", "--features", features.as_str()]);
+        }
+
+        let outputs = written_wasm_files(&[project_root.join("pkg")], config, || async {
+            run_command_with("wasm-pack", &args, Some(project_root), options).await
+        })
+        .await?;
+
+        expect_outputs(outputs, "wasm-pack writes them to pkg/.")
+    }
+
+    /// wasm-bindgen modules are loaded through their JavaScript glue, not
+    /// as components.
+    fn produces_core_modules(&self, _config: &BuildSection) -> bool {
+        false
+    }
+
+    fn version_commands(&self) -> &'static [(&'static str, &'static [&'static str])] {
+        &[
+            ("rustc", &["-vV"]),
+            ("cargo", &["-V"]),
+            ("wasm-pack", &["--version"]),
+        ]
+    }
+
+    /// `--target web` builds for the browser, whatever `build.target` says.
+    fn rust_target<'a>(&self, _config: &'a BuildSection) -> Option<&'a str> {
+        Some("wasm32-unknown-unknown")
+    }
+}
+
+/// `npm run build:wasm`, usually componentize-js, which already emits
+/// components.
+struct NpmBuilder;
+
+#[async_trait]
+impl Builder for NpmBuilder {
+    fn name(&self) -> &'static str {
+        "npm"
+    }
+
+    async fn build(
+        &self,
+        project_root: &Path,
+        config: &ProjectConfig,
+        _profile: &BuildProfile,
+        options: &RunOptions,
+    ) -> Result<Vec<PathBuf>> {
+        let outputs =
+            written_wasm_files(&build_output_dirs(project_root, config), config, || async {
+                run_command_with("npm", &["run", "build:wasm"], Some(project_root), options).await
+            })
+            .await?;
+
+        expect_outputs(outputs, "JavaScript builds should write to pkg/ or dist/.")
+    }
+
+    fn produces_core_modules(&self, _config: &BuildSection) -> bool {
+        false
+    }
+
+    fn version_commands(&self) -> &'static [(&'static str, &'static [&'static str])] {
+        &[("node", &["--version"]), ("npm", &["--version"])]
+    }
+}
+
+/// `build.command`, run through the shell from the project root. It may
+/// produce no `.wasm` file at all, e.g. when the module is embedded in a page.
+struct CustomBuilder {
+    command: String,
+}
+
+#[async_trait]
+impl Builder for CustomBuilder {
+    fn name(&self) -> &'static str {
+        "build command"
+    }
+
+    async fn build(
+        &self,
+        project_root: &Path,
+        config: &ProjectConfig,
+        profile: &BuildProfile,
+        options: &RunOptions,
+    ) -> Result<Vec<PathBuf>> {
+        let options = RunOptions {
+            env: build_env(project_root, config, profile, None),
+            ..options.clone()
+        };
+
+        written_wasm_files(&build_output_dirs(project_root, config), config, || async {
+            run_command_with("sh", &["-c", &self.command], Some(project_root), &options).await
+        })
+        .await
+    }
+
+    /// Components are passed through unchanged, so wrapping only affects
+    /// core modules.
+    fn produces_core_modules(&self, _config: &BuildSection) -> bool {
+        true
+    }
+}
+
+fn expect_outputs(outputs: Vec<PathBuf>, hint: &str) -> Result<Vec<PathBuf>> {
+    if outputs.is_empty() {
+        return Err(anyhow!("The build produced no .wasm file. {}", hint));
+    }
+    Ok(outputs)
+}
+
+/// Runs `build` and returns the `.wasm` files it wrote to `dirs`, for tools
+/// that do not report their outputs. Files it left untouched are stale
+/// outputs of earlier builds, not artifacts of this one.
+async fn written_wasm_files<F, Fut, T>(
+    dirs: &[PathBuf],
+    config: &ProjectConfig,
+    build: F,
+) -> Result<Vec<PathBuf>>
+where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = Result<T>>,
+{
+    let before = wasm_files(dirs, config);
+    build().await?;
+
+    Ok(wasm_files(dirs, config)
+        .into_iter()
+        .filter(|(path, modified)| before.get(path) != Some(modified))
+        .map(|(path, _)| path)
+        .collect())
+}
+
+/// Collects the `.wasm` files listed in cargo's `compiler-artifact` messages.
+fn cargo_wasm_artifacts(messages: &str) -> Vec<PathBuf> {
+    let mut artifacts: Vec<PathBuf> = messages
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter(|message| message["reason"] == "compiler-artifact")
+        .filter_map(|message| message["filenames"].as_array().cloned())
+        .flatten()
+        .filter_map(|file| file.as_str().map(PathBuf::from))
+        .filter(|path| is_wasm_file(path))
+        .collect();
+
+    artifacts.sort();
+    artifacts.dedup();
+    artifacts
+}
+
+/// `.wasm` files in `dirs` with their modification times, skipping the
+/// artifacts `build` derives from them.
+fn wasm_files(dirs: &[PathBuf], config: &ProjectConfig) -> BTreeMap<PathBuf, SystemTime> {
+    let mut files = BTreeMap::new();
+
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            if !is_wasm_file(&path) || is_derived_artifact(&path, config) {
+                continue;
+            }
+            if let Ok(modified) = entry.metadata().and_then(|m| m.modified()) {
+                files.insert(path, modified);
+            }
+        }
+    }
+
+    files
+}
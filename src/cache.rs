@@ -0,0 +1,240 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Overrides the cache location, e.g. for CI caches or tests.
+pub const CACHE_DIR_ENV: &str = "WASM_WIZARD_CACHE_DIR";
+
+/// Content-addressed store for build and optimization outputs.
+///
+/// File contents live under `objects/`, named by their SHA-256. Entries under
+/// `entries/` map a [`CacheKey`] to the files produced for it, so identical
+/// outputs of different builds are stored once.
+#[derive(Clone)]
+pub struct Cache {
+    dir: PathBuf,
+}
+
+/// Files produced for one key.
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    files: Vec<CachedFile>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedFile {
+    /// Relative to the root the files were stored from, or absolute when
+    /// the file lies outside of it
+    path: PathBuf,
+    object: String,
+}
+
+impl Cache {
+    /// Opens the cache in `$WASM_WIZARD_CACHE_DIR`, `$XDG_CACHE_HOME/wasm-wizard`
+    /// or `~/.cache/wasm-wizard`.
+    pub fn open() -> Result<Self> {
+        let dir = match std::env::var_os(CACHE_DIR_ENV) {
+            Some(dir) => PathBuf::from(dir),
+            None => std::env::var_os("XDG_CACHE_HOME")
+                .map(PathBuf::from)
+                .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Cannot locate a cache directory; set {} or use --no-cache",
+                        CACHE_DIR_ENV
+                    )
+                })?
+                .join("wasm-wizard"),
+        };
+
+        Ok(Self { dir })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Copies the single file cached for `key` to `output`; false on a miss.
+    pub fn restore_file(&self, key: &str, output: &Path) -> Result<bool> {
+        let Some(entry) = self.entry(key)? else {
+            return Ok(false);
+        };
+        let [file] = entry.files.as_slice() else {
+            return Ok(false);
+        };
+
+        self.copy_object(&file.object, output)
+    }
+
+    /// Caches `file` as the single output for `key`.
+    pub fn store_file(&self, key: &str, file: &Path) -> Result<()> {
+        let root = file.parent().unwrap_or(Path::new(""));
+        self.store_files(key, root, std::slice::from_ref(&file.to_path_buf()))
+    }
+
+    /// Restores the files cached for `key` below `root` and returns their
+    /// paths; `None` on a miss.
+    pub fn restore_files(&self, key: &str, root: &Path) -> Result<Option<Vec<PathBuf>>> {
+        let Some(entry) = self.entry(key)? else {
+            return Ok(None);
+        };
+
+        let mut restored = Vec::with_capacity(entry.files.len());
+        for file in &entry.files {
+            let path = root.join(&file.path);
+            if !self.copy_object(&file.object, &path)? {
+                // Objects removed behind our back make the entry useless
+                return Ok(None);
+            }
+            restored.push(path);
+        }
+
+        Ok(Some(restored))
+    }
+
+    /// Caches `files` as the outputs for `key`, recorded relative to `root`.
+    pub fn store_files(&self, key: &str, root: &Path, files: &[PathBuf]) -> Result<()> {
+        let mut entry = Entry { files: Vec::new() };
+
+        for file in files {
+            let bytes = std::fs::read(file)
+                .with_context(|| format!("Failed to read {} for caching", file.display()))?;
+            let object = hex::encode(Sha256::digest(&bytes));
+
+            let object_path = self.object_path(&object);
+            if !object_path.exists() {
+                self.write_atomically(&object_path, &bytes)?;
+            }
+
+            entry.files.push(CachedFile {
+                path: file.strip_prefix(root).unwrap_or(file).to_path_buf(),
+                object,
+            });
+        }
+
+        self.write_atomically(&self.entry_path(key), &serde_json::to_vec(&entry)?)
+    }
+
+    /// Removes everything cached; returns the number of files and bytes freed.
+    pub fn clean(&self) -> Result<(usize, u64)> {
+        if !self.dir.exists() {
+            return Ok((0, 0));
+        }
+
+        let (mut files, mut bytes) = (0, 0);
+        for entry in WalkDir::new(&self.dir).into_iter().filter_map(|e| e.ok()) {
+            if entry.file_type().is_file() {
+                files += 1;
+                bytes += entry.metadata().map(|m| m.len()).unwrap_or(0);
+            }
+        }
+
+        std::fs::remove_dir_all(&self.dir)
+            .with_context(|| format!("Failed to remove {}", self.dir.display()))?;
+        Ok((files, bytes))
+    }
+
+    fn entry(&self, key: &str) -> Result<Option<Entry>> {
+        match std::fs::read(self.entry_path(key)) {
+            // A corrupt entry is a miss; storing the key again replaces it
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes).ok()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn copy_object(&self, object: &str, output: &Path) -> Result<bool> {
+        let object_path = self.object_path(object);
+        if !object_path.is_file() {
+            return Ok(false);
+        }
+
+        if let Some(parent) = output.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(&object_path, output)
+            .with_context(|| format!("Failed to restore {} from cache", output.display()))?;
+        Ok(true)
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join("entries").join(&key[..2]).join(key)
+    }
+
+    fn object_path(&self, object: &str) -> PathBuf {
+        self.dir.join("objects").join(&object[..2]).join(object)
+    }
+
+    /// Concurrent builds may store the same file; renaming keeps readers
+    /// from seeing partial writes.
+    fn write_atomically(&self, path: &Path, bytes: &[u8]) -> Result<()> {
+        let dir = path.parent().unwrap_or(&self.dir);
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create cache directory {}", dir.display()))?;
+
+        let mut file = tempfile::NamedTempFile::new_in(dir)?;
+        file.write_all(bytes)?;
+        file.persist(path)
+            .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e.error))?;
+        Ok(())
+    }
+}
+
+/// SHA-256 over everything that can change an output.
+///
+/// Each part is length-prefixed, so different splits of the same bytes give
+/// different keys.
+pub struct CacheKey(Sha256);
+
+impl CacheKey {
+    /// Starts a key for the kind of work named by `kind`, tied to this
+    /// version of wasm-wizard.
+    pub fn new(kind: &str) -> Self {
+        let mut key = Self(Sha256::new());
+        key.add(kind.as_bytes());
+        key.add(env!("CARGO_PKG_VERSION").as_bytes());
+        key
+    }
+
+    pub fn add(&mut self, data: &[u8]) -> &mut Self {
+        self.0.update((data.len() as u64).to_le_bytes());
+        self.0.update(data);
+        self
+    }
+
+    pub fn add_file(&mut self, path: &Path) -> Result<&mut Self> {
+        let bytes = std::fs::read(path)
+            .with_context(|| format!("Failed to read {} for the cache key", path.display()))?;
+        Ok(self.add(&bytes))
+    }
+
+    /// Adds the relative path and contents of every file below `root`,
+    /// skipping directories for which `skip_dir` returns true.
+    pub fn add_tree(&mut self, root: &Path, skip_dir: impl Fn(&Path) -> bool) -> Result<&mut Self> {
+        let files = WalkDir::new(root)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|entry| {
+                entry.depth() == 0 || !entry.file_type().is_dir() || !skip_dir(entry.path())
+            });
+
+        for entry in files {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+            self.add(relative.to_string_lossy().as_bytes());
+            self.add_file(entry.path())?;
+        }
+
+        Ok(self)
+    }
+
+    pub fn finish(&mut self) -> String {
+        hex::encode(self.0.finalize_reset())
+    }
+}
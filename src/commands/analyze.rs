@@ -1,46 +1,266 @@
 use super::Command;
-use anyhow::Result;
+use crate::analyzer::{profile_modules, CoreModuleInfo, ModuleProfile, WorldEntry, WorldInfo};
+use crate::config::ProjectConfig;
+use crate::utils::{find_project_root, format_size, get_wasm_info, print_table, WasmInfo};
+use crate::workspace::Workspace;
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use colored::*;
+use std::path::Path;
 
 #[async_trait]
 impl Command for super::AnalyzeCommand {
     async fn execute(&self) -> Result<()> {
-        println!("{}", "📊 Analyzing WASM component".bright_green().bold());
-        println!("  Component: {}", self.component.display());
+        let Some(component) = &self.component else {
+            return self.analyze_workspace();
+        };
+
+        let info = self.inspect(component)?;
+
+        if self.format == "json" {
+            println!("{}", serde_json::to_string_pretty(&info)?);
+            return Ok(());
+        }
+
+        self.show_info(component, &info);
+        Ok(())
+    }
+}
 
-        let metadata = std::fs::metadata(&self.component)?;
-        let size = metadata.len();
+impl super::AnalyzeCommand {
+    fn inspect(&self, component: &Path) -> Result<WasmInfo> {
+        let mut info = get_wasm_info(component)?;
+
+        if self.detailed {
+            let wasm_bytes = std::fs::read(component)?;
+            info.profiles = Some(profile_modules(&wasm_bytes, self.top)?);
+        }
+
+        Ok(info)
+    }
+
+    /// Analyzes the build artifacts of every workspace member. JSON output is
+    /// an array with one document per artifact.
+    fn analyze_workspace(&self) -> Result<()> {
+        let project_root = find_project_root()?;
+        let config = ProjectConfig::load_or_default(&project_root)?;
+        let workspace = Workspace::load(&project_root, &config)?.ok_or_else(|| {
+            anyhow!(
+                "Specify a COMPONENT to analyze, or list projects under [workspace] members in wasm-wizard.toml"
+            )
+        })?;
+
+        let mut infos = Vec::new();
+        let mut rows = Vec::new();
+        for member in workspace.ordered() {
+            let artifacts = member.artifacts();
+            if artifacts.is_empty() {
+                rows.push(vec![member.name.clone(), "not built".to_string()]);
+                continue;
+            }
+
+            for artifact in artifacts {
+                let info = self.inspect(&artifact)?;
+                rows.push(vec![
+                    member.name.clone(),
+                    artifact
+                        .strip_prefix(&project_root)
+                        .unwrap_or(&artifact)
+                        .display()
+                        .to_string(),
+                    info.kind.clone(),
+                    format_size(info.size as u64),
+                    info.imports.len().to_string(),
+                    info.exports.len().to_string(),
+                ]);
+
+                if self.format != "json" {
+                    self.show_info(&artifact, &info);
+                    println!();
+                }
+                infos.push(info);
+            }
+        }
+
+        if self.format == "json" {
+            println!("{}", serde_json::to_string_pretty(&infos)?);
+            return Ok(());
+        }
+
+        println!("{}", "Workspace Summary:".bright_cyan().bold());
+        print_table(
+            &["Member", "Artifact", "Kind", "Size", "Imports", "Exports"],
+            &rows,
+        );
+        Ok(())
+    }
+
+    fn show_info(&self, component: &Path, info: &WasmInfo) {
+        println!("{}", "📊 Analyzing WASM component".bright_green().bold());
+        println!("  Component: {}", component.display());
 
         println!();
         println!("{}", "Analysis Results:".bright_cyan().bold());
-        println!("  📏 Size: {} bytes ({:.2} KB)", size, size as f64 / 1024.0);
-
-        // Analyze WASM structure
-        if let Ok(wasm_bytes) = std::fs::read(&self.component) {
-            let parser = wasmparser::Parser::new(0);
-            let mut sections = Vec::new();
-
-            for payload in parser.parse_all(&wasm_bytes) {
-                match payload {
-                    Ok(wasmparser::Payload::TypeSection(_)) => sections.push("Type"),
-                    Ok(wasmparser::Payload::ImportSection(_)) => sections.push("Import"),
-                    Ok(wasmparser::Payload::FunctionSection(_)) => sections.push("Function"),
-                    Ok(wasmparser::Payload::TableSection(_)) => sections.push("Table"),
-                    Ok(wasmparser::Payload::MemorySection(_)) => sections.push("Memory"),
-                    Ok(wasmparser::Payload::GlobalSection(_)) => sections.push("Global"),
-                    Ok(wasmparser::Payload::ExportSection(_)) => sections.push("Export"),
-                    Ok(wasmparser::Payload::StartSection { .. }) => sections.push("Start"),
-                    Ok(wasmparser::Payload::ElementSection(_)) => sections.push("Element"),
-                    Ok(wasmparser::Payload::DataSection(_)) => sections.push("Data"),
-                    Ok(wasmparser::Payload::CodeSectionStart { .. }) => sections.push("Code"),
-                    _ => {}
+        println!(
+            "  📏 Size: {} bytes ({:.2} KB)",
+            info.size,
+            info.size as f64 / 1024.0
+        );
+        println!("  🧩 Kind: {}", info.kind);
+
+        // Components repeat section kinds many times, so list each kind once
+        let mut sections: Vec<&str> = Vec::new();
+        for section in &info.sections {
+            if !sections.contains(&section.name.as_str()) {
+                sections.push(&section.name);
+            }
+        }
+        println!("  📦 Sections: {}", sections.join(", "));
+
+        if info.kind == "component" {
+            self.show_world(info.world.as_ref());
+            self.show_nested_modules(&info.modules);
+        } else if let Some(world) = &info.world {
+            // Modules built with wit-bindgen carry the world they were generated for
+            self.show_world(Some(world));
+        }
+
+        for profile in info.profiles.iter().flatten() {
+            self.show_profile(profile);
+        }
+    }
+
+    fn show_world(&self, world: Option<&WorldInfo>) {
+        println!();
+        let Some(world) = world else {
+            println!("{}", "World: <none embedded>".bright_cyan().bold());
+            return;
+        };
+
+        println!(
+            "{} {}",
+            "World:".bright_cyan().bold(),
+            world.name.bright_white()
+        );
+        self.show_world_entries("📥 Imports", &world.imports);
+        self.show_world_entries("📤 Exports", &world.exports);
+    }
+
+    fn show_world_entries(&self, title: &str, entries: &[WorldEntry]) {
+        println!("  {} ({})", title, entries.len());
+
+        for entry in entries {
+            match entry.kind.as_str() {
+                "interface" => {
+                    println!("    interface {}", entry.name.bright_cyan());
+                    for ty in &entry.types {
+                        println!("      {}", ty.definition);
+                        for function in &ty.functions {
+                            if function.name == "constructor" {
+                                println!("        {}", function.signature);
+                            } else {
+                                println!("        {}: {}", function.name, function.signature);
+                            }
+                        }
+                    }
+                    for function in &entry.functions {
+                        println!("      {}: {}", function.name, function.signature);
+                    }
+                }
+                "function" => {
+                    for function in &entry.functions {
+                        println!(
+                            "    {}: {}",
+                            function.name.bright_cyan(),
+                            function.signature
+                        );
+                    }
+                }
+                _ => {
+                    for ty in &entry.types {
+                        println!("    {}", ty.definition);
+                    }
                 }
             }
+        }
+    }
 
-            println!("  📦 Sections: {}", sections.join(", "));
+    fn show_nested_modules(&self, modules: &[CoreModuleInfo]) {
+        println!();
+        println!(
+            "{}",
+            format!("Core Modules ({}):", modules.len())
+                .bright_cyan()
+                .bold()
+        );
+        for module in modules {
+            println!(
+                "  #{} at offset {}: {} bytes ({:.2} KB)",
+                module.index,
+                module.offset,
+                module.size,
+                module.size as f64 / 1024.0
+            );
         }
+    }
 
-        Ok(())
+    fn show_profile(&self, profile: &ModuleProfile) {
+        println!();
+        let title = match profile.module {
+            Some(index) => format!("Size Breakdown (core module #{}):", index),
+            None => "Size Breakdown:".to_string(),
+        };
+        println!("{}", title.bright_cyan().bold());
+
+        let percent = |size: usize| size as f64 * 100.0 / profile.size.max(1) as f64;
+
+        let mut sections: Vec<_> = profile.sections.iter().collect();
+        sections.sort_by_key(|section| std::cmp::Reverse(section.size));
+        println!("  📦 Sections:");
+        for section in sections {
+            println!(
+                "    {:<24} {:>10} {:>6.1}%",
+                section.name,
+                format_size(section.size as u64),
+                percent(section.size)
+            );
+        }
+
+        println!(
+            "  🔧 Largest Functions ({} of {}):",
+            profile.functions.len(),
+            profile.function_count
+        );
+        if !profile.functions.is_empty() {
+            println!("    {:>10} {:>10}  Function", "Shallow", "Retained");
+        }
+        for function in &profile.functions {
+            let retained_by = match &function.retained_by {
+                Some(retainer) => format!("kept alive by {}", retainer),
+                None => "unreachable".to_string(),
+            };
+            println!(
+                "    {:>10} {:>10}  {} {}",
+                format_size(function.size as u64),
+                format_size(function.retained_size as u64),
+                function.name,
+                format!("({})", retained_by).dimmed()
+            );
+        }
+
+        println!(
+            "  💾 Data Segments ({} of {}):",
+            profile.data_segments.len(),
+            profile.data_segment_count
+        );
+        for segment in &profile.data_segments {
+            println!(
+                "    #{:<4} {:<8} {:>10} {:>6.1}%",
+                segment.index,
+                segment.kind,
+                format_size(segment.size as u64),
+                percent(segment.size)
+            );
+        }
     }
 }
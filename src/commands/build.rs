@@ -1,136 +1,732 @@
 use super::Command;
+use crate::builder::{self, build_env};
+use crate::cache::{Cache, CacheKey};
+use crate::componentizer::Componentizer;
+use crate::config::{BuildProfile, ProjectConfig, CONFIG_FILE_NAME};
 use crate::optimizer::Optimizer;
-use crate::utils::{find_project_root, run_command};
-use anyhow::{anyhow, Result};
+use crate::utils::{
+    find_project_root, format_size, interrupted, print_table, run_command_output, run_command_with,
+    RunOptions,
+};
+use crate::watcher::{Watcher, IGNORED_DIRS};
+use crate::workspace::Workspace;
+use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
 use colored::*;
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+
+/// Quiet period after the last change before `build --watch` rebuilds.
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
 
 #[async_trait]
 impl Command for super::BuildCommand {
     async fn execute(&self) -> Result<()> {
         let project_root = find_project_root()?;
-        let config_path = project_root.join("wasm-wizard.toml");
+        let config = ProjectConfig::load_project(&project_root)?;
 
-        if !config_path.exists() {
-            return Err(anyhow!(
-                "Not in a wasm-wizard project. Run 'wasm-wizard new' to create one."
-            ));
+        if let Some(workspace) = Workspace::load(&project_root, &config)? {
+            return self.build_workspace(&project_root, workspace).await;
         }
 
         println!(
             "{} {}",
             "🔨 Building project".bright_green().bold(),
-            self.target.bright_cyan()
+            self.profile_name().bright_cyan()
         );
 
-        let pb = ProgressBar::new_spinner();
-        pb.set_style(
-            ProgressStyle::default_spinner()
-                .template("{spinner:.green} {msg}")
-                .unwrap(),
+        // In watch mode a broken first build is just the first iteration
+        let artifacts = match self.run_build(&project_root, &config, false).await {
+            Ok(artifacts) => Some(artifacts),
+            Err(e) if self.watch => {
+                println!("{} {:#}", "❌ Build failed:".bright_red().bold(), e);
+                None
+            }
+            Err(e) => return Err(e),
+        };
+
+        if let Some(artifacts) = &artifacts {
+            println!();
+            println!("{}", "Build Summary:".bright_cyan().bold());
+            let (config, _) = config.with_profile(&self.profile_name())?;
+            self.show_build_summary(artifacts, &config)?;
+        }
+
+        if self.watch {
+            self.watch_and_rebuild(&project_root, artifacts.as_deref())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+impl super::BuildCommand {
+    /// Builds, optimizes and componentizes as configured, behind a progress
+    /// spinner. Returns the final artifacts.
+    async fn run_build(
+        &self,
+        project_root: &Path,
+        config: &ProjectConfig,
+        quiet: bool,
+    ) -> Result<Vec<PathBuf>> {
+        let pb = spinner("{spinner:.green} {msg}");
+        let result = self.build_artifacts(project_root, config, &pb).await;
+
+        if quiet || result.is_err() {
+            pb.finish_and_clear();
+        } else {
+            pb.finish_with_message("✅ Build completed successfully!");
+        }
+
+        result
+    }
+
+    /// Builds the project and post-processes its outputs, reporting progress on `pb`.
+    async fn build_artifacts(
+        &self,
+        project_root: &Path,
+        config: &ProjectConfig,
+        pb: &ProgressBar,
+    ) -> Result<Vec<PathBuf>> {
+        let (config, profile) = config.with_profile(&self.profile_name())?;
+        let config = &config;
+
+        // Runs before the cache lookup, as it may generate sources
+        self.run_hooks(
+            "pre_build",
+            &config.hooks.pre_build,
+            project_root,
+            RunOptions {
+                env: build_env(project_root, config, &profile, None),
+                ..self.run_options(config, pb)
+            },
+        )
+        .await?;
+
+        // Post hooks may sign, stamp or copy each output, which restoring the
+        // artifacts from the cache would skip
+        let optimize = self.optimize || config.build.optimize;
+        let post_hooks = !config.hooks.post_build.is_empty()
+            || (optimize && !config.hooks.post_optimize.is_empty());
+        let cache = if post_hooks { None } else { self.cache()? };
+        let key = match &cache {
+            Some(_) => self.cache_key(project_root, config, &profile).await?,
+            None => None,
+        };
+
+        if let (Some(cache), Some(key)) = (&cache, &key) {
+            if let Some(artifacts) = cache.restore_files(key, project_root)? {
+                pb.suspend(|| {
+                    println!(
+                        "♻️  Inputs unchanged, restored {} artifact(s) from cache",
+                        artifacts.len()
+                    )
+                });
+                return self.copy_to_output(artifacts);
+            }
+        }
+
+        let builder = builder::for_project(project_root, &config.build)?;
+        pb.set_message(format!("Building with {}...", builder.name()));
+        let outputs = builder
+            .build(
+                project_root,
+                config,
+                &profile,
+                &self.run_options(config, pb),
+            )
+            .await?;
+        let artifacts = self
+            .finish_artifacts(project_root, config, &profile, &outputs, pb)
+            .await?;
+
+        if let (Some(cache), Some(key)) = (&cache, &key) {
+            // Build commands without outputs may do anything; always rerun them
+            if !artifacts.is_empty() {
+                cache.store_files(key, project_root, &artifacts)?;
+            }
+        }
+
+        self.copy_to_output(artifacts)
+    }
+
+    /// `--profile`, or the built-in profile matching `--target`.
+    fn profile_name(&self) -> String {
+        match &self.profile {
+            Some(profile) => profile.clone(),
+            None if self.target == "release" => "release".to_string(),
+            None => "dev".to_string(),
+        }
+    }
+
+    fn cache(&self) -> Result<Option<Cache>> {
+        if self.no_cache {
+            return Ok(None);
+        }
+        Cache::open().map(Some)
+    }
+
+    /// Hashes everything that decides the artifacts: the project files, the
+    /// sources of Cargo path dependencies, the configuration, the build flags
+    /// and the toolchain versions. None when the dependencies cannot be
+    /// resolved, as the build then cannot be cached.
+    async fn cache_key(
+        &self,
+        project_root: &Path,
+        config: &ProjectConfig,
+        profile: &BuildProfile,
+    ) -> Result<Option<String>> {
+        let optimize = self.optimize || config.build.optimize;
+
+        let mut key = CacheKey::new("build");
+        key.add(&serde_json::to_vec(profile)?)
+            .add(&[optimize as u8])
+            .add(toml::to_string(config)?.as_bytes());
+
+        let builder = builder::for_project(project_root, &config.build)?;
+        for (tool, args) in builder.version_commands() {
+            // A missing tool fails the build itself, after the cache lookup
+            let version = run_command_output(tool, args, Some(project_root))
+                .await
+                .unwrap_or_default();
+            key.add(version.as_bytes());
+        }
+        if optimize {
+            key.add(
+                Optimizer::with_config(&config.optimize)
+                    .version()
+                    .await
+                    .as_bytes(),
+            );
+        }
+
+        for var in ["RUSTFLAGS", "CARGO_ENCODED_RUSTFLAGS", "CARGO_TARGET_DIR"] {
+            key.add(std::env::var(var).unwrap_or_default().as_bytes());
+        }
+        if let Some(adapter) = &config.build.adapter {
+            key.add_file(&project_root.join(adapter))?;
+        }
+
+        // Runs first, as it writes a missing Cargo.lock
+        if project_root.join("Cargo.toml").is_file()
+            && !add_cargo_dependencies(&mut key, project_root).await?
+        {
+            return Ok(None);
+        }
+
+        // Artifacts copied there by the last build must not change the key
+        let output = self
+            .output
+            .as_deref()
+            .and_then(|output| output.canonicalize().ok());
+        key.add_tree(project_root, |dir| {
+            is_ignored_dir(dir) || (output.is_some() && dir.canonicalize().ok() == output)
+        })?;
+        Ok(Some(key.finish()))
+    }
+
+    /// Builds every workspace member once the members it depends on are
+    /// built, running the members of each level in parallel.
+    async fn build_workspace(&self, workspace_root: &Path, workspace: Workspace) -> Result<()> {
+        if self.watch {
+            return Err(anyhow!(
+                "`build --watch` is not supported for workspaces; run it in a member directory"
+            ));
+        }
+
+        println!(
+            "{} {} ({} members)",
+            "🔨 Building workspace".bright_green().bold(),
+            self.profile_name().bright_cyan(),
+            workspace.members.len()
         );
-        pb.enable_steady_tick(Duration::from_millis(100));
 
-        // Build the project
-        pb.set_message("Building WASM component...");
-        self.build_component(&project_root).await?;
+        let progress = MultiProgress::new();
+        let mut outcomes: Vec<Option<MemberBuild>> =
+            workspace.members.iter().map(|_| None).collect();
 
-        if self.optimize {
-            pb.set_message("Optimizing WASM binary...");
-            self.optimize_component(&project_root).await?;
+        for level in &workspace.levels {
+            let mut tasks = JoinSet::new();
+
+            for &index in level {
+                let failed_dependency = workspace.dependencies[index].iter().find(|&&dependency| {
+                    !matches!(outcomes[dependency], Some(MemberBuild::Built { .. }))
+                });
+                if let Some(&dependency) = failed_dependency {
+                    outcomes[index] = Some(MemberBuild::Skipped {
+                        dependency: workspace.members[dependency].name.clone(),
+                    });
+                    continue;
+                }
+
+                let member = workspace.members[index].clone();
+                let pb = progress.add(spinner("{spinner:.green} {prefix:.bold} {msg}"));
+                pb.set_prefix(member.name.clone());
+                // Members may produce artifacts of the same name
+                let mut build = self.clone();
+                build.output = self.output.as_ref().map(|output| output.join(&member.name));
+
+                tasks.spawn(async move {
+                    let started = Instant::now();
+                    let result = build
+                        .build_artifacts(&member.root, &member.config, &pb)
+                        .await;
+                    let elapsed = started.elapsed();
+
+                    let outcome = match result {
+                        Ok(artifacts) => {
+                            pb.finish_with_message("✅ built");
+                            MemberBuild::Built { artifacts, elapsed }
+                        }
+                        Err(error) => {
+                            pb.finish_with_message("❌ failed");
+                            MemberBuild::Failed { error, elapsed }
+                        }
+                    };
+                    (index, outcome)
+                });
+            }
+
+            while let Some(joined) = tasks.join_next().await {
+                let (index, outcome) = joined?;
+                outcomes[index] = Some(outcome);
+            }
         }
 
-        pb.finish_with_message("✅ Build completed successfully!");
+        let mut rows = Vec::new();
+        let mut failures = 0;
+        for &index in workspace.levels.iter().flatten() {
+            let member = &workspace.members[index];
+            let row = match &outcomes[index] {
+                Some(MemberBuild::Built { artifacts, elapsed }) => vec![
+                    member.name.clone(),
+                    "built".to_string(),
+                    artifacts
+                        .iter()
+                        .map(|artifact| {
+                            artifact
+                                .strip_prefix(workspace_root)
+                                .unwrap_or(artifact)
+                                .display()
+                                .to_string()
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    total_size(artifacts).map(format_size).unwrap_or_default(),
+                    format!("{:.2}s", elapsed.as_secs_f64()),
+                ],
+                Some(MemberBuild::Failed { error, elapsed }) => {
+                    failures += 1;
+                    println!();
+                    println!(
+                        "{} {:#}",
+                        format!("❌ {} failed:", member.name).bright_red().bold(),
+                        error
+                    );
+                    vec![
+                        member.name.clone(),
+                        "failed".to_string(),
+                        String::new(),
+                        String::new(),
+                        format!("{:.2}s", elapsed.as_secs_f64()),
+                    ]
+                }
+                Some(MemberBuild::Skipped { dependency }) => {
+                    failures += 1;
+                    vec![
+                        member.name.clone(),
+                        format!("skipped ({} failed)", dependency),
+                        String::new(),
+                        String::new(),
+                        String::new(),
+                    ]
+                }
+                None => continue,
+            };
+            rows.push(row);
+        }
 
         println!();
-        println!("{}", "Build Summary:".bright_cyan().bold());
-        self.show_build_summary(&project_root).await?;
+        println!("{}", "Workspace Summary:".bright_cyan().bold());
+        print_table(&["Member", "Status", "Artifacts", "Size", "Time"], &rows);
+
+        if failures > 0 {
+            println!();
+            return Err(anyhow!(
+                "{} of {} workspace member(s) failed to build",
+                failures,
+                workspace.members.len()
+            ));
+        }
 
         Ok(())
     }
-}
 
-impl super::BuildCommand {
-    async fn build_component(&self, project_root: &Path) -> Result<()> {
-        let cargo_toml = project_root.join("Cargo.toml");
-
-        if cargo_toml.exists() {
-            // Rust project
-            let mut args = vec!["build"];
-            if self.target == "release" {
-                args.push("--release");
-            }
-            args.extend(["--target", "wasm32-wasip1"]);
+    /// Rebuilds whenever sources, WIT files, manifests or the config change.
+    async fn watch_and_rebuild(
+        &self,
+        project_root: &Path,
+        artifacts: Option<&[PathBuf]>,
+    ) -> Result<()> {
+        let patterns: Vec<String> = ["src", "wit", "Cargo.toml", "package.json", CONFIG_FILE_NAME]
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        let mut watcher = Watcher::new(project_root, &patterns);
 
-            run_command("cargo", &args, Some(project_root)).await?;
-        } else {
-            // Check for other language support
-            let package_json = project_root.join("package.json");
-            if package_json.exists() {
-                // JavaScript/TypeScript project
-                run_command("npm", &["run", "build:wasm"], Some(project_root)).await?;
-            } else {
-                return Err(anyhow!("Unsupported project type"));
+        let mut previous_size = artifacts.and_then(total_size);
+
+        println!();
+        println!(
+            "👀 Watching src/, wit/, Cargo.toml, package.json and {} (Ctrl-C to stop)",
+            CONFIG_FILE_NAME
+        );
+
+        let interrupted = interrupted();
+        tokio::pin!(interrupted);
+
+        loop {
+            let changed = tokio::select! {
+                _ = &mut interrupted => break,
+                changed = watcher.wait_for_changes(WATCH_DEBOUNCE) => changed,
+            };
+
+            println!();
+            println!(
+                "🔄 {} file(s) changed, rebuilding...",
+                changed.len().to_string().bright_cyan()
+            );
+
+            // The config is watched too, so pick up edits to it
+            let config = match ProjectConfig::load_project(project_root) {
+                Ok(config) => config,
+                Err(e) => {
+                    println!("{} {:#}", "❌ Build failed:".bright_red().bold(), e);
+                    continue;
+                }
+            };
+
+            let started = Instant::now();
+            let result = self.run_build(project_root, &config, true).await;
+            let elapsed = started.elapsed().as_secs_f64();
+
+            match result {
+                Ok(artifacts) => {
+                    let size = total_size(&artifacts);
+                    println!(
+                        "✅ Rebuilt in {:.2}s{}",
+                        elapsed,
+                        describe_size(size, previous_size)
+                    );
+                    previous_size = size.or(previous_size);
+                }
+                Err(e) => println!(
+                    "{} {:#}",
+                    format!("❌ Build failed after {:.2}s:", elapsed)
+                        .bright_red()
+                        .bold(),
+                    e
+                ),
             }
         }
 
+        println!();
+        println!("👋 Stopped watching");
         Ok(())
     }
 
-    async fn optimize_component(&self, project_root: &Path) -> Result<()> {
-        let wasm_file = self.find_wasm_output(project_root)?;
-        let optimizer = Optimizer::new();
+    /// Whether the toolchain outputs are core modules to wrap into
+    /// components; cargo-component, componentize-js and cargo for
+    /// `wasm32-wasip2` emit components themselves.
+    fn componentizes(&self, project_root: &Path, config: &ProjectConfig) -> bool {
+        config.build.output_format == "component"
+            && builder::for_project(project_root, &config.build)
+                .is_ok_and(|builder| builder.produces_core_modules(&config.build))
+    }
+
+    /// Final artifact derived from the toolchain output `wasm_file`, next to
+    /// it; named after the profile's `output_name` when set.
+    fn artifact_path(
+        &self,
+        wasm_file: &Path,
+        project_root: &Path,
+        config: &ProjectConfig,
+        profile: &BuildProfile,
+    ) -> PathBuf {
+        let optimize = self.optimize || config.build.optimize;
+        let name = wasm_file.file_stem().unwrap_or_default().to_string_lossy();
 
-        let optimized_path = if let Some(output) = &self.output {
-            output.clone()
-        } else {
+        if let Some(file_name) = profile.output_file_name(&name) {
+            wasm_file.with_file_name(file_name)
+        } else if self.componentizes(project_root, config) {
+            wasm_file.with_extension("component.wasm")
+        } else if optimize {
             wasm_file.with_extension("optimized.wasm")
+        } else {
+            wasm_file.to_path_buf()
+        }
+    }
+
+    /// Post-processes every toolchain output.
+    async fn finish_artifacts(
+        &self,
+        project_root: &Path,
+        config: &ProjectConfig,
+        profile: &BuildProfile,
+        outputs: &[PathBuf],
+        pb: &ProgressBar,
+    ) -> Result<Vec<PathBuf>> {
+        let mut artifacts = Vec::with_capacity(outputs.len());
+        for wasm_file in outputs {
+            artifacts.push(
+                self.finish_artifact(project_root, config, profile, wasm_file, pb)
+                    .await?,
+            );
+        }
+
+        Ok(artifacts)
+    }
+
+    /// Copies the artifacts into the `--output` directory, when one is given.
+    fn copy_to_output(&self, artifacts: Vec<PathBuf>) -> Result<Vec<PathBuf>> {
+        let Some(output_dir) = &self.output else {
+            return Ok(artifacts);
         };
 
-        optimizer
-            .optimize_file(&wasm_file, &optimized_path, 3)
+        std::fs::create_dir_all(output_dir)?;
+        let mut copies = Vec::with_capacity(artifacts.len());
+        for artifact in artifacts {
+            let file_name = artifact
+                .file_name()
+                .ok_or_else(|| anyhow!("Invalid artifact path {}", artifact.display()))?;
+            let copy = output_dir.join(file_name);
+            std::fs::copy(&artifact, &copy)?;
+            copies.push(copy);
+        }
+
+        Ok(copies)
+    }
+
+    /// Turns the toolchain output into the final artifact: optimized first,
+    /// while it is still a core module, then wrapped into a component.
+    async fn finish_artifact(
+        &self,
+        project_root: &Path,
+        config: &ProjectConfig,
+        profile: &BuildProfile,
+        wasm_file: &Path,
+        pb: &ProgressBar,
+    ) -> Result<PathBuf> {
+        let optimize = self.optimize || config.build.optimize;
+        let componentize = self.componentizes(project_root, config);
+        let artifact = self.artifact_path(wasm_file, project_root, config, profile);
+        let mut current = wasm_file.to_path_buf();
+
+        self.run_hooks(
+            "post_build",
+            &config.hooks.post_build,
+            project_root,
+            RunOptions {
+                env: build_env(project_root, config, profile, Some(&current)),
+                ..self.run_options(config, pb)
+            },
+        )
+        .await?;
+
+        if optimize {
+            pb.set_message("Optimizing WASM binary...");
+            let optimized = wasm_file.with_extension("optimized.wasm");
+            Optimizer::with_config(&config.optimize)
+                .with_cache(self.cache()?)
+                .optimize_file(&current, &optimized, config.optimize.level)
+                .await?;
+            current = optimized;
+
+            self.run_hooks(
+                "post_optimize",
+                &config.hooks.post_optimize,
+                project_root,
+                RunOptions {
+                    env: build_env(project_root, config, profile, Some(&current)),
+                    ..self.run_options(config, pb)
+                },
+            )
             .await?;
-        Ok(())
+        }
+
+        if componentize {
+            pb.set_message("Creating component...");
+            Componentizer::with_config(&config.build).componentize_file(
+                &current,
+                &project_root.join("wit"),
+                &artifact,
+            )?;
+        } else if current != artifact {
+            std::fs::copy(&current, &artifact)?;
+        }
+
+        Ok(artifact)
     }
 
-    fn find_wasm_output(&self, project_root: &Path) -> Result<PathBuf> {
-        let target_dir = project_root.join("target/wasm32-wasip1");
-        let build_dir = if self.target == "release" {
-            target_dir.join("release")
-        } else {
-            target_dir.join("debug")
-        };
+    /// Runs the `[hooks]` commands of `hook` through the shell from the
+    /// project root; the first failing command fails the build.
+    async fn run_hooks(
+        &self,
+        hook: &str,
+        commands: &[String],
+        project_root: &Path,
+        mut options: RunOptions,
+    ) -> Result<()> {
+        options.env.push(("WASM_WIZARD_HOOK", hook.to_string()));
 
-        // Find the .wasm file
-        let entries = std::fs::read_dir(&build_dir)?;
-        for entry in entries {
-            let entry = entry?;
-            let path = entry.path();
-            if path.extension().is_some_and(|ext| ext == "wasm") {
-                return Ok(path);
+        for command in commands {
+            if let Some(pb) = &options.progress {
+                pb.set_message(format!("Running {} hook: {}", hook, command));
             }
+            run_command_with("sh", &["-c", command], Some(project_root), &options)
+                .await
+                .with_context(|| format!("{} hook '{}' failed", hook, command))?;
         }
 
-        Err(anyhow!("No WASM output found in {}", build_dir.display()))
+        Ok(())
     }
 
-    async fn show_build_summary(&self, project_root: &Path) -> Result<()> {
-        if let Ok(wasm_file) = self.find_wasm_output(project_root) {
-            let metadata = std::fs::metadata(&wasm_file)?;
-            let size = metadata.len();
+    /// Streams command output under `--verbose` and applies `build.timeout`.
+    fn run_options(&self, config: &ProjectConfig, pb: &ProgressBar) -> RunOptions {
+        RunOptions {
+            verbose: self.verbose,
+            timeout: config.build.timeout.map(Duration::from_secs),
+            progress: Some(pb.clone()),
+            ..Default::default()
+        }
+    }
 
-            println!("  📦 Output: {}", wasm_file.display());
-            println!("  📏 Size: {} bytes ({:.2} KB)", size, size as f64 / 1024.0);
+    fn show_build_summary(&self, artifacts: &[PathBuf], config: &ProjectConfig) -> Result<()> {
+        if artifacts.is_empty() {
+            println!("  📦 The build command produced no .wasm files");
+        }
+
+        for artifact in artifacts {
+            let bytes = std::fs::read(artifact)?;
+            let size = bytes.len();
 
-            // Show optimization potential
-            if !self.optimize {
-                println!("  💡 Tip: Use --optimize to reduce size further");
+            println!("  📦 Output: {}", artifact.display());
+            println!("  📏 Size: {} bytes ({:.2} KB)", size, size as f64 / 1024.0);
+            if wasmparser::Parser::is_component(&bytes) {
+                println!("  🧩 Kind: component");
             }
         }
 
+        // Show optimization potential
+        if !(self.optimize || config.build.optimize) {
+            println!("  💡 Tip: Use --optimize to reduce size further");
+        }
+
         Ok(())
     }
 }
+
+/// Outcome of building one workspace member.
+enum MemberBuild {
+    Built {
+        artifacts: Vec<PathBuf>,
+        elapsed: Duration,
+    },
+    Failed {
+        error: anyhow::Error,
+        elapsed: Duration,
+    },
+    /// Not attempted because a member it depends on did not build
+    Skipped { dependency: String },
+}
+
+fn spinner(template: &str) -> ProgressBar {
+    let pb = ProgressBar::new_spinner();
+    pb.set_style(ProgressStyle::default_spinner().template(template).unwrap());
+    pb.enable_steady_tick(Duration::from_millis(100));
+    pb
+}
+
+fn total_size(artifacts: &[PathBuf]) -> Option<u64> {
+    artifacts
+        .iter()
+        .map(|artifact| std::fs::metadata(artifact).ok().map(|m| m.len()))
+        .sum()
+}
+
+fn describe_size(size: Option<u64>, previous: Option<u64>) -> String {
+    match (size, previous) {
+        (Some(size), Some(previous)) if size == previous => {
+            format!(": {} (unchanged)", format_size(size))
+        }
+        (Some(size), Some(previous)) => {
+            let delta = size as i64 - previous as i64;
+            let sign = if delta > 0 { "+" } else { "-" };
+            format!(
+                ": {} ({}{})",
+                format_size(size),
+                sign,
+                format_size(delta.unsigned_abs())
+            )
+        }
+        (Some(size), None) => format!(": {}", format_size(size)),
+        (None, _) => String::new(),
+    }
+}
+
+fn is_ignored_dir(dir: &Path) -> bool {
+    dir.file_name()
+        .is_some_and(|name| IGNORED_DIRS.contains(&name.to_string_lossy().as_ref()))
+}
+
+/// Adds the Cargo.lock and the sources of the path dependencies outside
+/// `project_root`, which cargo rebuilds when they change. Returns false when
+/// `cargo metadata` cannot resolve them.
+async fn add_cargo_dependencies(key: &mut CacheKey, project_root: &Path) -> Result<bool> {
+    let Ok(metadata) = run_command_output(
+        "cargo",
+        &["metadata", "--format-version", "1"],
+        Some(project_root),
+    )
+    .await
+    else {
+        return Ok(false);
+    };
+    let metadata: serde_json::Value = serde_json::from_str(&metadata)?;
+
+    // A member of a Cargo workspace shares the workspace's lock file
+    if let Some(workspace_root) = metadata["workspace_root"].as_str() {
+        let lock = Path::new(workspace_root).join("Cargo.lock");
+        if lock.is_file() {
+            key.add_file(&lock)?;
+        }
+    }
+
+    let root = project_root.canonicalize()?;
+    let mut dirs: Vec<PathBuf> = metadata["packages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        // Packages without a source are local: path dependencies and workspace members
+        .filter(|package| package["source"].is_null())
+        .filter_map(|package| {
+            Some(
+                Path::new(package["manifest_path"].as_str()?)
+                    .parent()?
+                    .to_path_buf(),
+            )
+        })
+        .filter(|dir| !dir.starts_with(project_root) && !dir.starts_with(&root))
+        .collect();
+    dirs.sort();
+    dirs.dedup();
+
+    for dir in dirs {
+        key.add(dir.to_string_lossy().as_bytes());
+        key.add_tree(&dir, is_ignored_dir)?;
+    }
+    Ok(true)
+}
@@ -0,0 +1,30 @@
+use super::{CacheAction, Command};
+use crate::cache::Cache;
+use crate::utils::format_size;
+use anyhow::Result;
+use async_trait::async_trait;
+use colored::*;
+
+#[async_trait]
+impl Command for super::CacheCommand {
+    async fn execute(&self) -> Result<()> {
+        let cache = Cache::open()?;
+
+        match self.action {
+            CacheAction::Clean => {
+                println!("{}", "🧹 Cleaning cache".bright_green().bold());
+                println!("  Directory: {}", cache.dir().display());
+
+                let (files, bytes) = cache.clean()?;
+                println!();
+                println!(
+                    "✅ Removed {} cached file(s), {}",
+                    files,
+                    format_size(bytes)
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
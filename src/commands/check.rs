@@ -1,16 +1,449 @@
 use super::Command;
-use anyhow::Result;
+use crate::builder;
+use crate::config::{ProjectConfig, ProjectSection, CONFIG_FILE_NAME};
+use crate::utils::{
+    build_output_dirs, find_project_root, format_size, is_wasm_file, print_table, run_command,
+    run_command_output, validate_wasm,
+};
+use crate::workspace::Workspace;
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use colored::*;
+use std::path::{Path, PathBuf};
+use wit_parser::{PackageName, Resolve};
 
 #[async_trait]
 impl Command for super::CheckCommand {
     async fn execute(&self) -> Result<()> {
-        println!("{}", "🔍 Checking WASM components".bright_green().bold());
+        let mut failures = Vec::new();
+
+        if let Some(component) = &self.component {
+            failures.extend(self.check_components(std::slice::from_ref(component)));
+        } else {
+            let project_root = find_project_root()?;
+            // An invalid config is reported by the project checks instead
+            let workspace = match ProjectConfig::load_or_default(&project_root) {
+                Ok(config) => Workspace::load(&project_root, &config)?,
+                Err(_) => None,
+            };
+
+            if let Some(workspace) = workspace {
+                failures.extend(self.check_workspace(&workspace).await?);
+            } else {
+                let problems = self.check_project(&project_root).await?;
+                if problems > 0 {
+                    failures.push(format!("{} project problem(s) found", problems));
+                }
+
+                if self.all {
+                    println!();
+                    match self.find_build_outputs(&project_root) {
+                        Ok(outputs) => failures.extend(self.check_components(&outputs)),
+                        Err(e) => {
+                            println!("  ❌ {}", format!("{:#}", e).bright_red());
+                            failures.push(format!("{:#}", e));
+                        }
+                    }
+                }
+            }
+        }
 
-        // Implementation for checking components
-        println!("✅ All components are valid!");
+        println!();
+        if !failures.is_empty() {
+            return Err(anyhow!("{}", failures.join("; ")));
+        }
 
+        if self.component.is_some() {
+            println!("✅ All components are valid!");
+        } else {
+            println!("✅ All checks passed!");
+        }
         Ok(())
     }
 }
+
+impl super::CheckCommand {
+    /// Checks every workspace member; returns a summary per failing member.
+    async fn check_workspace(&self, workspace: &Workspace) -> Result<Vec<String>> {
+        let mut failures = Vec::new();
+        let mut rows = Vec::new();
+
+        for member in workspace.ordered() {
+            let problems = self.check_project(&member.root).await?;
+            if problems > 0 {
+                failures.push(format!(
+                    "{}: {} project problem(s) found",
+                    member.name, problems
+                ));
+            }
+            let mut row = vec![member.name.clone(), problems.to_string()];
+
+            if self.all {
+                println!();
+                let outputs = match self.find_build_outputs(&member.root) {
+                    Ok(outputs) => self.check_components(&outputs),
+                    Err(e) => Some(e.to_string()),
+                };
+                match outputs {
+                    Some(failure) => {
+                        row.push(failure.clone());
+                        failures.push(format!("{}: {}", member.name, failure));
+                    }
+                    None => row.push("valid".to_string()),
+                }
+            }
+
+            rows.push(row);
+            println!();
+        }
+
+        println!("{}", "Workspace Summary:".bright_cyan().bold());
+        if self.all {
+            print_table(&["Member", "Problems", "Build outputs"], &rows);
+        } else {
+            print_table(&["Member", "Problems"], &rows);
+        }
+
+        Ok(failures)
+    }
+
+    /// Validates every component; returns a summary when some are invalid.
+    fn check_components(&self, components: &[PathBuf]) -> Option<String> {
+        println!("{}", "🔍 Checking WASM components".bright_green().bold());
+
+        let failures = components
+            .iter()
+            .filter(|component| !self.check_component(component))
+            .count();
+
+        (failures > 0).then(|| {
+            format!(
+                "{} of {} component(s) failed validation",
+                failures,
+                components.len()
+            )
+        })
+    }
+
+    /// Validates one file and prints the outcome; returns whether it is valid.
+    fn check_component(&self, path: &Path) -> bool {
+        let wasm_bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                println!("  ❌ {}: {}", path.display(), e);
+                return false;
+            }
+        };
+
+        match validate_wasm(&wasm_bytes) {
+            Ok(()) => {
+                let kind = if wasmparser::Parser::is_component(&wasm_bytes) {
+                    "component"
+                } else {
+                    "module"
+                };
+                println!(
+                    "  ✅ {} ({}, {})",
+                    path.display(),
+                    kind,
+                    format_size(wasm_bytes.len() as u64)
+                );
+                true
+            }
+            Err(e) => {
+                println!("  ❌ {}", path.display().to_string().bright_red());
+                println!("     at offset {:#x}: {}", e.offset(), e.message());
+                false
+            }
+        }
+    }
+
+    /// Collects the `.wasm` files produced by the project's builds.
+    fn find_build_outputs(&self, project_root: &Path) -> Result<Vec<PathBuf>> {
+        let config = ProjectConfig::load_or_default(project_root)
+            .map_err(|e| anyhow!("Cannot locate the build outputs: {:#}", e))?;
+
+        let mut outputs = Vec::new();
+        for dir in build_output_dirs(project_root, &config) {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries {
+                let path = entry?.path();
+                if path.is_file() && is_wasm_file(&path) {
+                    outputs.push(path);
+                }
+            }
+        }
+        outputs.sort();
+
+        if outputs.is_empty() {
+            return Err(anyhow!(
+                "No WASM build outputs found in {}. Run 'wasm-wizard build' first.",
+                project_root.display()
+            ));
+        }
+
+        Ok(outputs)
+    }
+
+    /// Audits the project setup; returns the number of unresolved problems.
+    async fn check_project(&self, project_root: &Path) -> Result<usize> {
+        println!(
+            "{} {}",
+            "🩺 Checking project".bright_green().bold(),
+            project_root.display()
+        );
+
+        let mut problems = 0;
+        let config = self.check_config(project_root, &mut problems)?;
+        let wit_package = self.check_wit(project_root, &mut problems);
+        self.check_cargo_metadata(project_root, wit_package.as_ref(), &mut problems);
+        self.check_rust_target(project_root, &config, &mut problems)
+            .await;
+        self.check_required_tools(&config, &mut problems);
+
+        Ok(problems)
+    }
+
+    /// Returns the project configuration, or the defaults when it is unusable.
+    fn check_config(&self, project_root: &Path, problems: &mut usize) -> Result<ProjectConfig> {
+        let config_path = project_root.join(CONFIG_FILE_NAME);
+
+        if !config_path.exists() {
+            if !self.fix {
+                report_problem(
+                    problems,
+                    &format!("{} not found", CONFIG_FILE_NAME),
+                    Some("Run 'wasm-wizard check --fix' to generate one"),
+                );
+                return Ok(ProjectConfig::default());
+            }
+
+            let config = generate_config(project_root)?;
+            std::fs::write(&config_path, toml::to_string(&config)?)?;
+            report_fixed(&format!("Generated {}", CONFIG_FILE_NAME));
+            return Ok(config);
+        }
+
+        match ProjectConfig::load(&config_path) {
+            Ok(config) => {
+                report_ok(&format!("{} is valid", CONFIG_FILE_NAME));
+                Ok(config)
+            }
+            Err(e) => {
+                report_problem(problems, &format!("{:#}", e), None);
+                Ok(ProjectConfig::default())
+            }
+        }
+    }
+
+    /// Parses `wit/` and returns its package name.
+    fn check_wit(&self, project_root: &Path, problems: &mut usize) -> Option<PackageName> {
+        let wit_dir = project_root.join("wit");
+        if !wit_dir.is_dir() {
+            println!("  ⏭️  No wit/ directory");
+            return None;
+        }
+
+        let mut resolve = Resolve::new();
+        match resolve.push_dir(&wit_dir) {
+            // The package of wit/ itself comes after those it depends on
+            Ok((packages, _)) => {
+                let name = resolve.packages[*packages.last()?].name.clone();
+                report_ok(&format!("wit/ parses as package {}", name));
+                Some(name)
+            }
+            Err(e) => {
+                report_problem(problems, &format!("wit/ failed to parse: {:#}", e), None);
+                None
+            }
+        }
+    }
+
+    fn check_cargo_metadata(
+        &self,
+        project_root: &Path,
+        wit_package: Option<&PackageName>,
+        problems: &mut usize,
+    ) {
+        let Some(manifest) = read_cargo_manifest(project_root) else {
+            return;
+        };
+        let Some(metadata_package) = manifest
+            .get("package")
+            .and_then(|package| package.get("metadata"))
+            .and_then(|metadata| metadata.get("component"))
+            .and_then(|component| component.get("package"))
+            .and_then(|package| package.as_str())
+        else {
+            return;
+        };
+        let Some(wit_package) = wit_package else {
+            return;
+        };
+
+        // The manifest may leave the version out
+        let unversioned = format!("{}:{}", wit_package.namespace, wit_package.name);
+        if metadata_package == wit_package.to_string() || metadata_package == unversioned {
+            report_ok("Cargo.toml component package matches the WIT package");
+        } else {
+            report_problem(
+                problems,
+                &format!(
+                    "Cargo.toml [package.metadata.component] package \"{}\" does not match WIT package \"{}\"",
+                    metadata_package, wit_package
+                ),
+                Some("Rename one of them so both refer to the same package"),
+            );
+        }
+    }
+
+    async fn check_rust_target(
+        &self,
+        project_root: &Path,
+        config: &ProjectConfig,
+        problems: &mut usize,
+    ) {
+        let Ok(builder) = builder::for_project(project_root, &config.build) else {
+            return;
+        };
+        let Some(target) = builder.rust_target(&config.build) else {
+            return;
+        };
+
+        let installed = match run_command_output(
+            "rustup",
+            &["target", "list", "--installed"],
+            Some(project_root),
+        )
+        .await
+        {
+            Ok(installed) => installed.lines().any(|line| line.trim() == target),
+            Err(_) => {
+                println!(
+                    "  ⚠️  rustup not found; cannot verify that {} is installed",
+                    target
+                );
+                return;
+            }
+        };
+
+        if installed {
+            report_ok(&format!("Rust target {} is installed", target));
+            return;
+        }
+
+        if self.fix {
+            match run_command("rustup", &["target", "add", target], Some(project_root)).await {
+                Ok(()) => {
+                    report_fixed(&format!("Installed Rust target {}", target));
+                    return;
+                }
+                Err(e) => {
+                    report_problem(
+                        problems,
+                        &format!("Failed to install Rust target {}: {}", target, e),
+                        None,
+                    );
+                    return;
+                }
+            }
+        }
+
+        report_problem(
+            problems,
+            &format!("Rust target {} is not installed", target),
+            Some(&format!(
+                "Run 'rustup target add {}' or 'wasm-wizard check --fix'",
+                target
+            )),
+        );
+    }
+
+    fn check_required_tools(&self, config: &ProjectConfig, problems: &mut usize) {
+        for tool in &config.toolchain.required_tools {
+            if which::which(tool).is_ok() {
+                report_ok(&format!("{} is installed", tool));
+            } else {
+                report_problem(
+                    problems,
+                    &format!("Required tool {} is not on PATH", tool),
+                    Some("Install it or see 'wasm-wizard install' for the supported tools"),
+                );
+            }
+        }
+    }
+}
+
+fn report_ok(message: &str) {
+    println!("  ✅ {}", message);
+}
+
+fn report_fixed(message: &str) {
+    println!("  🔧 {}", message.bright_green());
+}
+
+fn report_problem(problems: &mut usize, message: &str, hint: Option<&str>) {
+    *problems += 1;
+    println!("  ❌ {}", message.replace('\n', "\n     ").bright_red());
+    if let Some(hint) = hint {
+        println!("     💡 {}", hint);
+    }
+}
+
+fn read_cargo_manifest(project_root: &Path) -> Option<toml::Value> {
+    let content = std::fs::read_to_string(project_root.join("Cargo.toml")).ok()?;
+    toml::from_str(&content).ok()
+}
+
+/// Builds a default configuration named after the project's manifest.
+fn generate_config(project_root: &Path) -> Result<ProjectConfig> {
+    let package_json = project_root.join("package.json");
+
+    let (name, language) = if let Some(manifest) = read_cargo_manifest(project_root) {
+        let name = manifest
+            .get("package")
+            .and_then(|package| package.get("name"))
+            .and_then(|name| name.as_str())
+            .map(str::to_string);
+        (name, Some("rust"))
+    } else if package_json.exists() {
+        let manifest: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&package_json)?)?;
+        let language = if project_root.join("tsconfig.json").exists() {
+            "typescript"
+        } else {
+            "javascript"
+        };
+        (
+            manifest["name"].as_str().map(str::to_string),
+            Some(language),
+        )
+    } else {
+        (None, None)
+    };
+
+    let name = match name {
+        Some(name) => name,
+        None => project_root
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .ok_or_else(|| {
+                anyhow!(
+                    "Cannot derive a project name for {}",
+                    project_root.display()
+                )
+            })?,
+    };
+
+    Ok(ProjectConfig {
+        project: ProjectSection {
+            name,
+            version: Some("0.1.0".to_string()),
+            language: language.map(str::to_string),
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+}
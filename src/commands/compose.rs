@@ -1,6 +1,8 @@
 use super::Command;
 use crate::composer::Composer;
+use crate::config::ProjectConfig;
 use crate::optimizer::Optimizer;
+use crate::utils::find_project_root;
 use anyhow::Result;
 use async_trait::async_trait;
 use colored::*;
@@ -10,6 +12,11 @@ use std::time::Duration;
 #[async_trait]
 impl Command for super::ComposeCommand {
     async fn execute(&self) -> Result<()> {
+        let config = match find_project_root() {
+            Ok(root) => ProjectConfig::load_or_default(&root)?,
+            Err(_) => ProjectConfig::default(),
+        };
+
         println!("{}", "🔗 Composing WASM components".bright_green().bold());
 
         // Show components being composed
@@ -37,12 +44,12 @@ impl Command for super::ComposeCommand {
             .await?;
 
         // Optimize if requested
-        if self.optimize {
+        if self.optimize || config.composition.enable_optimization {
             pb.set_message("Optimizing composed component...");
-            let optimizer = Optimizer::new();
+            let optimizer = Optimizer::with_config(&config.optimize);
             let optimized_output = self.output.with_extension("optimized.wasm");
             optimizer
-                .optimize_file(&self.output, &optimized_output, 3)
+                .optimize_file(&self.output, &optimized_output, config.optimize.level)
                 .await?;
 
             // Replace original with optimized
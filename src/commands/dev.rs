@@ -1,25 +1,170 @@
-use super::Command;
-use anyhow::Result;
+use super::{BuildCommand, Command};
+use crate::builder;
+use crate::config::ProjectConfig;
+use crate::server::{DevServer, HotReload};
+use crate::utils::{build_output_dirs, find_project_root, interrupted};
+use crate::watcher::Watcher;
+use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
 use colored::*;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+/// Quiet period after the last change before a rebuild starts.
+const REBUILD_DEBOUNCE: Duration = Duration::from_millis(200);
 
 #[async_trait]
 impl Command for super::DevCommand {
     async fn execute(&self) -> Result<()> {
+        let project_root = find_project_root()?;
+        let config = ProjectConfig::load_or_default(&project_root)?;
+
+        if let Some(command) = &config.dev.command {
+            return self.run_external_server(&project_root, command).await;
+        }
+
+        let host = self.host.as_deref().unwrap_or(&config.dev.host);
+        let port = self.port.unwrap_or(config.dev.port);
+        let hot_reload = self.hot_reload || config.dev.hot_reload;
+        // Reloading the page is pointless unless something rebuilds it first
+        let watch = self.watch || hot_reload;
+
+        let listener = TcpListener::bind((host, port))
+            .await
+            .with_context(|| format!("Failed to bind development server to {}:{}", host, port))?;
+
         println!("{}", "🚀 Starting development server".bright_green().bold());
-        println!("  Server: http://{}:{}", self.host, self.port);
+        println!("  Server: http://{}:{}", host, port);
         println!(
             "  Hot reload: {}",
-            if self.hot_reload {
-                "enabled"
-            } else {
-                "disabled"
-            }
+            if hot_reload { "enabled" } else { "disabled" }
         );
+        if watch {
+            println!("  Watching: {}", config.dev.watch_paths.join(", "));
+        }
+        println!();
+
+        let hot_reload = hot_reload.then(|| Arc::new(HotReload::new()));
+        let buildable = has_build_pipeline(&project_root, &config);
+        if buildable {
+            self.rebuild(hot_reload.as_deref()).await;
+        }
+
+        let server = Arc::new(DevServer::new(
+            &project_root,
+            config.files.entry.clone(),
+            build_output_dirs(&project_root, &config),
+            hot_reload.clone(),
+        ));
+        let mut server_task = tokio::spawn(server.serve(listener));
+
+        println!(
+            "{}",
+            format!(
+                "Development server running at http://{}:{} (Ctrl-C to stop)",
+                host, port
+            )
+            .bright_cyan()
+        );
+
+        // Created once so a Ctrl-C pressed during a rebuild is not lost
+        let interrupted = interrupted();
+        tokio::pin!(interrupted);
+
+        let mut watcher = Watcher::new(&project_root, &config.dev.watch_paths);
+        loop {
+            tokio::select! {
+                _ = &mut interrupted => break,
+                result = &mut server_task => {
+                    result??;
+                    return Err(anyhow!("Development server stopped unexpectedly"));
+                }
+                changed = watcher.wait_for_changes(REBUILD_DEBOUNCE), if watch => {
+                    println!();
+                    println!("🔄 {} file(s) changed", changed.len());
+                    if buildable {
+                        self.rebuild(hot_reload.as_deref()).await;
+                    } else if let Some(hot_reload) = &hot_reload {
+                        hot_reload.reload();
+                    }
+                }
+            }
+        }
+
+        server_task.abort();
+        println!();
+        println!("👋 Development server stopped");
+        Ok(())
+    }
+}
+
+impl super::DevCommand {
+    /// Runs the regular build pipeline, reporting the outcome to the console
+    /// and to connected pages without stopping the server.
+    async fn rebuild(&self, hot_reload: Option<&HotReload>) {
+        let build = BuildCommand {
+            target: "release".to_string(),
+            profile: None,
+            optimize: false,
+            output: None,
+            watch: false,
+            verbose: false,
+            no_cache: false,
+        };
 
-        // Implementation for dev server
-        println!("Development server running...");
+        match build.execute().await {
+            Ok(()) => {
+                if let Some(hot_reload) = hot_reload {
+                    hot_reload.reload();
+                }
+            }
+            Err(e) => {
+                println!("{} {:#}", "❌ Build failed:".bright_red().bold(), e);
+                if let Some(hot_reload) = hot_reload {
+                    hot_reload.build_failed(format!("{:#}", e));
+                }
+            }
+        }
+    }
+
+    /// Hands serving over to the `[dev] command` configured for the project.
+    async fn run_external_server(&self, project_root: &Path, command: &str) -> Result<()> {
+        println!("{}", "🚀 Starting development server".bright_green().bold());
+        println!("  Command: {}", command);
+
+        let mut server = tokio::process::Command::new("sh")
+            .args(["-c", command])
+            .current_dir(project_root)
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to run '{}'", command))?;
+
+        let status = tokio::select! {
+            status = server.wait() => status?,
+            _ = interrupted() => {
+                let _ = server.kill().await;
+                println!();
+                println!("👋 Development server stopped");
+                return Ok(());
+            }
+        };
+
+        if !status.success() {
+            return Err(anyhow!(
+                "Development server command '{}' failed: {}",
+                command,
+                status
+            ));
+        }
 
         Ok(())
     }
 }
+
+/// Projects without a build command, build tool or Cargo or npm manifest
+/// are served as-is.
+fn has_build_pipeline(project_root: &Path, config: &ProjectConfig) -> bool {
+    builder::for_project(project_root, &config.build).is_ok()
+}
@@ -1,11 +1,12 @@
 use anyhow::Result;
-use clap::Args;
+use clap::{Args, Subcommand};
 use std::path::PathBuf;
 
 pub mod add;
 pub mod analyze;
 pub mod bindings;
 pub mod build;
+pub mod cache;
 pub mod check;
 pub mod compose;
 pub mod dev;
@@ -47,12 +48,17 @@ pub struct NewCommand {
     pub no_install: bool,
 }
 
-#[derive(Args)]
+#[derive(Args, Clone)]
 pub struct BuildCommand {
     /// Build target (debug/release)
     #[arg(short, long, default_value = "release")]
     pub target: String,
 
+    /// Build profile: dev, release, size, speed or a `[profile.<name>]` table
+    /// [default: release, or dev with --target debug]
+    #[arg(short, long)]
+    pub profile: Option<String>,
+
     /// Enable optimization
     #[arg(short, long)]
     pub optimize: bool,
@@ -65,9 +71,13 @@ pub struct BuildCommand {
     #[arg(short, long)]
     pub watch: bool,
 
-    /// Enable verbose output
+    /// Show the output of the toolchain and hooks as they run
     #[arg(short, long)]
     pub verbose: bool,
+
+    /// Always rebuild instead of reusing cached outputs
+    #[arg(long)]
+    pub no_cache: bool,
 }
 
 #[derive(Args)]
@@ -91,25 +101,30 @@ pub struct ComposeCommand {
 
 #[derive(Args)]
 pub struct OptimizeCommand {
-    /// WASM file to optimize
+    /// WASM file to optimize [default: the build outputs of every workspace member]
     #[arg(value_name = "FILE")]
-    pub file: PathBuf,
+    pub file: Option<PathBuf>,
 
     /// Output file
     #[arg(short, long)]
     pub output: Option<PathBuf>,
 
-    /// Optimization level (0-4)
-    #[arg(short, long, default_value = "3")]
-    pub level: u8,
-
-    /// Target size in bytes
+    /// Optimization level (0-4) [default: from wasm-wizard.toml, or 3]
     #[arg(short, long)]
-    pub size: Option<usize>,
+    pub level: Option<u8>,
+
+    /// Target size in bytes, or with a kb/mb suffix; tries increasingly
+    /// aggressive optimizations until the output fits
+    #[arg(short, long, value_parser = crate::utils::parse_size, conflicts_with = "level")]
+    pub size: Option<u64>,
 
     /// Enable experimental optimizations
     #[arg(long)]
     pub experimental: bool,
+
+    /// Always optimize instead of reusing cached outputs
+    #[arg(long)]
+    pub no_cache: bool,
 }
 
 #[derive(Args)]
@@ -159,13 +174,13 @@ pub struct AddCommand {
 
 #[derive(Args)]
 pub struct DevCommand {
-    /// Port to run on
-    #[arg(short, long, default_value = "8080")]
-    pub port: u16,
+    /// Port to run on [default: from wasm-wizard.toml, or 8080]
+    #[arg(short, long)]
+    pub port: Option<u16>,
 
-    /// Host to bind to
-    #[arg(long, default_value = "localhost")]
-    pub host: String,
+    /// Host to bind to [default: from wasm-wizard.toml, or localhost]
+    #[arg(long)]
+    pub host: Option<String>,
 
     /// Watch for changes
     #[arg(short, long)]
@@ -193,15 +208,31 @@ pub struct BindingsCommand {
 
 #[derive(Args)]
 pub struct AnalyzeCommand {
-    /// Component to analyze
+    /// Component to analyze [default: the build artifacts of every workspace member]
     #[arg(value_name = "COMPONENT")]
-    pub component: PathBuf,
+    pub component: Option<PathBuf>,
 
-    /// Show detailed analysis
+    /// Show a per-section and per-function size breakdown
     #[arg(short, long)]
     pub detailed: bool,
 
+    /// Number of functions and data segments listed by --detailed
+    #[arg(long, value_name = "N", default_value = "10")]
+    pub top: usize,
+
     /// Output format (text/json)
-    #[arg(short, long, default_value = "text")]
+    #[arg(short, long, default_value = "text", value_parser = ["text", "json"])]
     pub format: String,
 }
+
+#[derive(Args)]
+pub struct CacheCommand {
+    #[command(subcommand)]
+    pub action: CacheAction,
+}
+
+#[derive(Subcommand)]
+pub enum CacheAction {
+    /// Remove all cached build and optimization outputs
+    Clean,
+}
@@ -1,39 +1,290 @@
 use super::Command;
+use crate::analyzer::profile_modules;
+use crate::cache::Cache;
+use crate::config::ProjectConfig;
 use crate::optimizer::Optimizer;
-use anyhow::Result;
+use crate::utils::{find_project_root, format_size, print_table};
+use crate::workspace::Workspace;
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use colored::*;
+use std::path::Path;
 
 #[async_trait]
 impl Command for super::OptimizeCommand {
     async fn execute(&self) -> Result<()> {
-        let output_file = self.output.clone().unwrap_or_else(|| {
-            let mut path = self.file.clone();
-            path.set_extension("optimized.wasm");
-            path
-        });
+        // Optimizing a file does not require a project, but honor its settings when in one
+        let project_root = find_project_root().ok();
+        let config = match &project_root {
+            Some(root) => ProjectConfig::load_or_default(root)?,
+            None => ProjectConfig::default(),
+        };
+
+        let Some(file) = &self.file else {
+            let workspace = match &project_root {
+                Some(root) => Workspace::load(root, &config)?,
+                None => None,
+            };
+            let workspace = workspace.ok_or_else(|| {
+                anyhow!(
+                    "Specify a FILE to optimize, or list projects under [workspace] members in wasm-wizard.toml"
+                )
+            })?;
+            return self.optimize_workspace(&workspace).await;
+        };
+
+        let output_file = self
+            .output
+            .clone()
+            .unwrap_or_else(|| file.with_extension("optimized.wasm"));
+        let level = self.level.unwrap_or(config.optimize.level);
 
         println!("{}", "⚡ Optimizing WASM component".bright_green().bold());
-        println!("  Input: {}", self.file.display());
+        println!("  Input: {}", file.display());
         println!("  Output: {}", output_file.display());
-        println!("  Level: {}", self.level);
+        match self.size {
+            Some(target) => println!("  Target: {} bytes", target),
+            None => println!("  Level: {}", level),
+        }
         println!();
 
-        let optimizer = Optimizer::new();
-        optimizer
-            .optimize_file(&self.file, &output_file, self.level)
-            .await?;
+        let (original_size, optimized_size) =
+            self.optimize_file(file, &output_file, &config).await?;
 
         // Show optimization results
-        let original_size = std::fs::metadata(&self.file)?.len();
-        let optimized_size = std::fs::metadata(&output_file)?.len();
-        let reduction = ((original_size - optimized_size) as f64 / original_size as f64) * 100.0;
-
         println!("{}", "Optimization Results:".bright_cyan().bold());
         println!("  Original: {} bytes", original_size);
         println!("  Optimized: {} bytes", optimized_size);
-        println!("  Reduction: {:.1}%", reduction);
+        println!(
+            "  Reduction: {:.1}%",
+            reduction(original_size, optimized_size)
+        );
+
+        Ok(())
+    }
+}
+
+impl super::OptimizeCommand {
+    /// Optimizes `input` into `output`; returns both sizes.
+    async fn optimize_file(
+        &self,
+        input: &Path,
+        output: &Path,
+        config: &ProjectConfig,
+    ) -> Result<(u64, u64)> {
+        let level = self.level.unwrap_or(config.optimize.level);
+        let cache = if self.no_cache {
+            None
+        } else {
+            Some(Cache::open()?)
+        };
+        let optimizer = Optimizer::with_config(&config.optimize).with_cache(cache);
+        match self.size {
+            Some(target) => {
+                self.optimize_to_size(&optimizer, input, output, target)
+                    .await?
+            }
+            None => optimizer.optimize_file(input, output, level).await?,
+        }
+
+        Ok((
+            std::fs::metadata(input)?.len(),
+            std::fs::metadata(output)?.len(),
+        ))
+    }
+
+    /// Tries the optimizer's strategies in order and stops at the first
+    /// output of at most `target` bytes. When none fits, the smallest output
+    /// is kept and the sections and functions taking up its space are shown.
+    async fn optimize_to_size(
+        &self,
+        optimizer: &Optimizer,
+        input: &Path,
+        output: &Path,
+        target: u64,
+    ) -> Result<()> {
+        let mut smallest: Option<(u64, &str, Vec<u8>)> = None;
+
+        if !optimizer.has_wasm_opt() {
+            println!(
+                "⚠️  wasm-opt not found: the search is limited to dead code elimination and stripping custom sections; install binaryen to also try -O1 to -Oz"
+            );
+        }
+
+        for strategy in optimizer.strategies() {
+            println!("🎯 Trying {}", strategy.name.bold());
+            optimizer
+                .with_strategy(strategy)
+                .optimize_file(input, output, strategy.level)
+                .await?;
+
+            let size = std::fs::metadata(output)?.len();
+            if size <= target {
+                println!(
+                    "✅ {} reached the target: {} bytes (target {} bytes)",
+                    strategy.name, size, target
+                );
+                println!();
+                return Ok(());
+            }
+            println!("  {} bytes, {} over the target", size, size - target);
+
+            if smallest.as_ref().is_none_or(|(best, ..)| size < *best) {
+                smallest = Some((size, strategy.name, std::fs::read(output)?));
+            }
+        }
+
+        let Some((size, strategy, wasm)) = smallest else {
+            return Err(anyhow!("No optimization strategies are available"));
+        };
+        std::fs::write(output, &wasm)?;
+
+        println!();
+        show_largest_contributors(&wasm)?;
+        let hint = if optimizer.has_wasm_opt() {
+            ""
+        } else {
+            "; wasm-opt, which was not found, may get further"
+        };
+        Err(anyhow!(
+            "Could not reach the target size of {} bytes; the closest output, {} bytes from {}, was written to {}{}",
+            target,
+            size,
+            strategy,
+            output.display(),
+            hint
+        ))
+    }
+
+    /// Optimizes the build outputs of every member, next to the outputs.
+    async fn optimize_workspace(&self, workspace: &Workspace) -> Result<()> {
+        if self.output.is_some() {
+            return Err(anyhow!(
+                "--output needs a FILE; workspace members are optimized next to their build outputs"
+            ));
+        }
+
+        println!(
+            "{} ({} members)",
+            "⚡ Optimizing workspace".bright_green().bold(),
+            workspace.members.len()
+        );
+
+        let mut rows = Vec::new();
+        let mut failures = 0;
+        for member in workspace.ordered() {
+            let outputs = member.build_outputs();
+            if outputs.is_empty() {
+                failures += 1;
+                println!(
+                    "  ❌ {}: no build outputs found. Run 'wasm-wizard build' first.",
+                    member.name.bright_red()
+                );
+                rows.push(vec![member.name.clone(), "not built".to_string()]);
+                continue;
+            }
+
+            for output in outputs {
+                let optimized = output.with_extension("optimized.wasm");
+                let file_name = output
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string();
+
+                match self
+                    .optimize_file(&output, &optimized, &member.config)
+                    .await
+                {
+                    Ok((original_size, optimized_size)) => {
+                        println!("  ✅ {}: {}", member.name, optimized.display());
+                        rows.push(vec![
+                            member.name.clone(),
+                            file_name,
+                            format_size(original_size),
+                            format_size(optimized_size),
+                            format!("{:.1}%", reduction(original_size, optimized_size)),
+                        ]);
+                    }
+                    Err(e) => {
+                        failures += 1;
+                        println!("  ❌ {}: {:#}", member.name.bright_red(), e);
+                        rows.push(vec![member.name.clone(), file_name, "failed".to_string()]);
+                    }
+                }
+            }
+        }
+
+        println!();
+        println!("{}", "Workspace Summary:".bright_cyan().bold());
+        print_table(
+            &["Member", "File", "Original", "Optimized", "Reduction"],
+            &rows,
+        );
+
+        if failures > 0 {
+            println!();
+            return Err(anyhow!(
+                "{} workspace member output(s) could not be optimized",
+                failures
+            ));
+        }
 
         Ok(())
     }
 }
+
+/// Prints the largest sections and functions of `wasm`, across all of its
+/// core modules.
+fn show_largest_contributors(wasm: &[u8]) -> Result<()> {
+    const TOP: usize = 5;
+    let profiles = profile_modules(wasm, TOP)?;
+    let location = |module: Option<usize>, name: &str| match module {
+        Some(index) => format!("{} (core module #{})", name, index),
+        None => name.to_string(),
+    };
+
+    let mut sections: Vec<(usize, String)> = profiles
+        .iter()
+        .flat_map(|profile| {
+            profile
+                .sections
+                .iter()
+                .map(|section| (section.size, location(profile.module, &section.name)))
+        })
+        .collect();
+    let mut functions: Vec<(usize, String)> = profiles
+        .iter()
+        .flat_map(|profile| {
+            profile
+                .functions
+                .iter()
+                .map(|function| (function.size, location(profile.module, &function.name)))
+        })
+        .collect();
+
+    println!("{}", "Largest Remaining Contributors:".bright_cyan().bold());
+    for (title, items) in [
+        ("📦 Sections", &mut sections),
+        ("🔧 Functions", &mut functions),
+    ] {
+        if items.is_empty() {
+            continue;
+        }
+        items.sort_by_key(|(size, _)| std::cmp::Reverse(*size));
+        println!("  {}:", title);
+        for (size, name) in items.iter().take(TOP) {
+            println!("    {:>10}  {}", format_size(*size as u64), name);
+        }
+    }
+    println!();
+
+    Ok(())
+}
+
+fn reduction(original_size: u64, optimized_size: u64) -> f64 {
+    if original_size == 0 {
+        return 0.0;
+    }
+    (original_size as f64 - optimized_size as f64) / original_size as f64 * 100.0
+}
@@ -0,0 +1,136 @@
+use crate::config::BuildSection;
+use anyhow::{anyhow, Context, Result};
+use std::path::{Path, PathBuf};
+use wasi_preview1_component_adapter_provider::{
+    WASI_SNAPSHOT_PREVIEW1_COMMAND_ADAPTER, WASI_SNAPSHOT_PREVIEW1_REACTOR_ADAPTER,
+};
+use wit_component::{ComponentEncoder, StringEncoding};
+use wit_parser::Resolve;
+
+/// Import module of WASI preview1 functions, satisfied by the adapter.
+const WASI_PREVIEW1: &str = "wasi_snapshot_preview1";
+
+/// Wraps core modules produced by the toolchains into components.
+pub struct Componentizer {
+    world: Option<String>,
+    adapter: Option<PathBuf>,
+}
+
+impl Componentizer {
+    pub fn with_config(config: &BuildSection) -> Self {
+        Self {
+            world: config.world.clone(),
+            adapter: config.adapter.as_ref().map(PathBuf::from),
+        }
+    }
+
+    /// Encodes the core module at `input` as a component written to `output`.
+    ///
+    /// Modules built with wit-bindgen already carry their world; others get
+    /// the world of the package in `wit_dir` embedded first. Inputs that are
+    /// already components are copied unchanged.
+    pub fn componentize_file(&self, input: &Path, wit_dir: &Path, output: &Path) -> Result<()> {
+        let mut module = std::fs::read(input)?;
+
+        if wasmparser::Parser::is_component(&module) {
+            if input != output {
+                std::fs::write(output, &module)?;
+            }
+            return Ok(());
+        }
+
+        if !has_component_metadata(&module)? && wit_dir.is_dir() {
+            self.embed_world(&mut module, wit_dir)?;
+        }
+
+        let mut encoder = ComponentEncoder::default()
+            .validate(true)
+            .module(&module)
+            .map_err(|e| anyhow!("Invalid core module {}: {:#}", input.display(), e))?;
+
+        if imports_module(&module, WASI_PREVIEW1)? {
+            let adapter = self.adapter(exports_start(&module)?)?;
+            encoder = encoder
+                .adapter(WASI_PREVIEW1, &adapter)
+                .map_err(|e| anyhow!("Invalid WASI adapter: {:#}", e))?;
+        }
+
+        let component = encoder.encode().map_err(|e| {
+            anyhow!(
+                "Failed to create component from {}: {:#}",
+                input.display(),
+                e
+            )
+        })?;
+
+        std::fs::write(output, component)?;
+        Ok(())
+    }
+
+    fn embed_world(&self, module: &mut Vec<u8>, wit_dir: &Path) -> Result<()> {
+        let mut resolve = Resolve::new();
+        let (packages, _) = resolve.push_dir(wit_dir)?;
+        let world = resolve
+            .select_world(&packages, self.world.as_deref())
+            .map_err(|e| anyhow!("{:#}. Set `build.world` in wasm-wizard.toml", e))?;
+
+        wit_component::embed_component_metadata(module, &resolve, world, StringEncoding::UTF8)
+            .map_err(|e| anyhow!("Failed to embed WIT world: {:#}", e))
+    }
+
+    /// Commands (modules exporting `_start`) need the command adapter,
+    /// libraries the reactor one.
+    fn adapter(&self, command: bool) -> Result<Vec<u8>> {
+        if let Some(path) = &self.adapter {
+            return std::fs::read(path)
+                .with_context(|| format!("Failed to read WASI adapter {}", path.display()));
+        }
+
+        Ok(if command {
+            WASI_SNAPSHOT_PREVIEW1_COMMAND_ADAPTER.to_vec()
+        } else {
+            WASI_SNAPSHOT_PREVIEW1_REACTOR_ADAPTER.to_vec()
+        })
+    }
+}
+
+/// wit-bindgen embeds its world in `component-type*` custom sections.
+fn has_component_metadata(module: &[u8]) -> Result<bool> {
+    for payload in wasmparser::Parser::new(0).parse_all(module) {
+        if let wasmparser::Payload::CustomSection(reader) = payload? {
+            if reader.name().starts_with("component-type") {
+                return Ok(true);
+            }
+        }
+    }
+
+    Ok(false)
+}
+
+fn imports_module(module: &[u8], name: &str) -> Result<bool> {
+    for payload in wasmparser::Parser::new(0).parse_all(module) {
+        if let wasmparser::Payload::ImportSection(reader) = payload? {
+            for import in reader {
+                if import?.module == name {
+                    return Ok(true);
+                }
+            }
+        }
+    }
+
+    Ok(false)
+}
+
+fn exports_start(module: &[u8]) -> Result<bool> {
+    for payload in wasmparser::Parser::new(0).parse_all(module) {
+        if let wasmparser::Payload::ExportSection(reader) = payload? {
+            for export in reader {
+                if export?.name == "_start" {
+                    return Ok(true);
+                }
+            }
+        }
+    }
+
+    Ok(false)
+}
@@ -1,12 +1,19 @@
-use anyhow::{anyhow, Result};
+use crate::utils::{run_command, validate_wasm};
+use anyhow::{anyhow, Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use wasm_compose::graph::{Component, CompositionGraph, EncodeOptions, InstanceId};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CompositionConfig {
+    /// Components to compose; the first one is the root whose exports are kept.
+    /// When empty, the components given on the command line are used.
+    #[serde(default)]
     pub components: Vec<ComponentConfig>,
+    #[serde(default)]
     pub output: OutputConfig,
+    #[serde(default)]
     pub linking: LinkingConfig,
 }
 
@@ -14,7 +21,11 @@ pub struct CompositionConfig {
 pub struct ComponentConfig {
     pub name: String,
     pub path: PathBuf,
+    /// Exports the component is expected to provide
+    #[serde(default)]
     pub exports: Vec<String>,
+    /// Imports the component is expected to require
+    #[serde(default)]
     pub imports: Vec<String>,
 }
 
@@ -24,12 +35,31 @@ pub struct OutputConfig {
     pub format: String, // "component" or "module"
 }
 
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            name: "composed".to_string(),
+            format: "component".to_string(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LinkingConfig {
     pub strategy: String, // "auto", "manual"
+    #[serde(default)]
     pub connections: Vec<Connection>,
 }
 
+impl Default for LinkingConfig {
+    fn default() -> Self {
+        Self {
+            strategy: "auto".to_string(),
+            connections: Vec::new(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Connection {
     pub from: String,
@@ -57,11 +87,18 @@ impl Composer {
         output: &Path,
         config: Option<&Path>,
     ) -> Result<()> {
+        // Linking rules are only understood by the in-process composer
+        if let Some(config_path) = config {
+            return self
+                .compose_with_config(components, output, config_path)
+                .await;
+        }
+
         if let Some(wasm_compose) = &self.wasm_compose_path {
-            self.compose_with_wasm_compose(wasm_compose, components, output, config)
+            self.compose_with_wasm_compose(wasm_compose, components, output)
                 .await
         } else {
-            self.compose_manual(components, output, config).await
+            self.compose_manual(components, output).await
         }
     }
 
@@ -70,190 +107,434 @@ impl Composer {
         wasm_compose: &str,
         components: &[PathBuf],
         output: &Path,
-        config: Option<&Path>,
     ) -> Result<()> {
-        let mut cmd = Command::new(wasm_compose);
-
-        if let Some(config_path) = config {
-            cmd.arg("-c").arg(config_path);
+        let (root, definitions) = components
+            .split_first()
+            .ok_or_else(|| anyhow!("No components to compose"))?;
+
+        let output = output.to_string_lossy();
+        let definitions: Vec<_> = definitions.iter().map(|d| d.to_string_lossy()).collect();
+        let root = root.to_string_lossy();
+
+        let mut args = vec!["-o", &output];
+        // The first component is the root; the others satisfy its imports
+        for definition in &definitions {
+            args.extend(["-d", definition]);
         }
+        args.push(&root);
 
-        cmd.arg("-o").arg(output);
+        run_command(wasm_compose, &args, None)
+            .await
+            .context("wasm-compose failed")?;
 
-        for component in components {
-            cmd.arg(component);
-        }
+        println!("✅ Composed with wasm-compose");
+        Ok(())
+    }
 
-        let output_result = cmd.output()?;
+    async fn compose_manual(&self, components: &[PathBuf], output: &Path) -> Result<()> {
+        println!("⚠️  wasm-compose not found, composing in-process");
 
-        if !output_result.status.success() {
-            let stderr = String::from_utf8_lossy(&output_result.stderr);
-            return Err(anyhow!("wasm-compose failed: {}", stderr));
-        }
+        // Perform composition
+        let composed_bytes = self.manual_compose(components)?;
 
-        println!("✅ Composed with wasm-compose");
+        // Write output
+        std::fs::write(output, composed_bytes)?;
+
+        println!("✅ In-process composition completed");
         Ok(())
     }
 
-    async fn compose_manual(
+    async fn compose_with_config(
         &self,
         components: &[PathBuf],
         output: &Path,
-        config: Option<&Path>,
+        config_path: &Path,
     ) -> Result<()> {
-        println!("⚠️  wasm-compose not found, using manual composition");
-
-        // Load composition config if provided
-        let config = if let Some(config_path) = config {
-            let config_content = std::fs::read_to_string(config_path)?;
-            toml::from_str::<CompositionConfig>(&config_content)?
+        let config_content = std::fs::read_to_string(config_path)?;
+        let mut config = toml::from_str::<CompositionConfig>(&config_content).map_err(|e| {
+            anyhow!(
+                "Invalid composition config {}:\n{}",
+                config_path.display(),
+                e
+            )
+        })?;
+
+        // Component paths in the config are relative to the config file
+        let config_dir = config_path.parent().unwrap_or(Path::new(""));
+        if config.components.is_empty() {
+            config.components = self.default_components(components);
         } else {
-            self.generate_default_config(components)?
-        };
-
-        // Analyze components
-        let mut analyzed_components = Vec::new();
-        for component_path in components {
-            let analysis = self.analyze_component(component_path)?;
-            analyzed_components.push(analysis);
+            for component in &mut config.components {
+                component.path = config_dir.join(&component.path);
+            }
         }
 
-        // Perform composition
-        let composed_bytes = self.manual_compose(&analyzed_components, &config)?;
-
-        // Write output
+        let composed_bytes = self.linked_compose(&config)?;
         std::fs::write(output, composed_bytes)?;
 
-        println!("✅ Manual composition completed");
+        println!(
+            "✅ Composed in-process with {} linking",
+            config.linking.strategy
+        );
         Ok(())
     }
 
-    fn generate_default_config(&self, components: &[PathBuf]) -> Result<CompositionConfig> {
-        let mut config = CompositionConfig {
-            components: Vec::new(),
-            output: OutputConfig {
-                name: "composed".to_string(),
-                format: "component".to_string(),
-            },
-            linking: LinkingConfig {
-                strategy: "auto".to_string(),
-                connections: Vec::new(),
-            },
-        };
-
-        for component_path in components.iter() {
-            let component_name = component_path
-                .file_stem()
-                .unwrap_or_default()
-                .to_string_lossy()
-                .to_string();
-
-            config.components.push(ComponentConfig {
-                name: component_name,
+    fn default_components(&self, components: &[PathBuf]) -> Vec<ComponentConfig> {
+        components
+            .iter()
+            .map(|component_path| ComponentConfig {
+                name: component_path
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string(),
                 path: component_path.clone(),
                 exports: Vec::new(),
                 imports: Vec::new(),
-            });
-        }
+            })
+            .collect()
+    }
 
-        Ok(config)
+    fn manual_compose(&self, components: &[PathBuf]) -> Result<Vec<u8>> {
+        let (root, definitions) = components
+            .split_first()
+            .ok_or_else(|| anyhow!("No components to compose"))?;
+
+        // Same semantics as `wasm-compose <root> -d <definition>...`: the root's
+        // instance imports are satisfied by matching exports of the definitions.
+        let compose_config = wasm_compose::config::Config {
+            definitions: definitions.to_vec(),
+            ..Default::default()
+        };
+
+        let composed = wasm_compose::composer::ComponentComposer::new(root, &compose_config)
+            .compose()
+            .map_err(|e| anyhow!("Composition failed: {:#}", e))?;
+
+        // The CLI validates its output unless told otherwise, so do the same
+        validate_wasm(&composed)
+            .map_err(|e| anyhow!("Composed component failed validation: {}", e))?;
+
+        Ok(composed)
     }
 
-    fn analyze_component(&self, path: &Path) -> Result<ComponentAnalysis> {
-        let wasm_bytes = std::fs::read(path)?;
-        let parser = wasmparser::Parser::new(0);
+    /// Builds the instantiation graph described by `config` and encodes it.
+    fn linked_compose(&self, config: &CompositionConfig) -> Result<Vec<u8>> {
+        if config.output.format != "component" {
+            return Err(anyhow!(
+                "Unsupported output format '{}': composition always produces a component",
+                config.output.format
+            ));
+        }
+
+        if config.components.is_empty() {
+            return Err(anyhow!("No components to compose"));
+        }
+
+        let mut graph = CompositionGraph::new();
+        let mut instances: Vec<(&str, InstanceId)> = Vec::new();
+
+        for component in &config.components {
+            if instances.iter().any(|(name, _)| *name == component.name) {
+                return Err(anyhow!(
+                    "Component '{}' is listed more than once",
+                    component.name
+                ));
+            }
 
-        let mut analysis = ComponentAnalysis {
-            path: path.to_path_buf(),
-            imports: Vec::new(),
-            exports: Vec::new(),
-            functions: Vec::new(),
+            let loaded = Component::from_file(&component.name, &component.path)
+                .with_context(|| format!("Failed to load component '{}'", component.name))?;
+            check_declared_interfaces(component, &loaded)?;
+
+            let id = graph.add_component(loaded)?;
+            instances.push((&component.name, graph.instantiate(id)?));
+        }
+
+        let instance_of = |name: &str| {
+            instances
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, id)| *id)
+                .ok_or_else(|| anyhow!("Connection refers to unknown component '{}'", name))
         };
 
-        for payload in parser.parse_all(&wasm_bytes) {
-            match payload {
-                Ok(wasmparser::Payload::ImportSection(reader)) => {
-                    for import in reader.into_iter().flatten() {
-                        analysis.imports.push(ImportInfo {
-                            module: import.module.to_string(),
-                            name: import.name.to_string(),
-                            ty: format!("{:?}", import.ty),
-                        });
-                    }
-                }
-                Ok(wasmparser::Payload::ExportSection(reader)) => {
-                    for export in reader.into_iter().flatten() {
-                        analysis.exports.push(ExportInfo {
-                            name: export.name.to_string(),
-                            ty: format!("{:?}", export.kind),
-                            index: export.index,
-                        });
-                    }
-                }
-                _ => {}
+        // Explicit connections are applied in both strategies
+        let mut connected: HashSet<(String, String)> = HashSet::new();
+        let mut used_exports: HashSet<(String, String)> = HashSet::new();
+        for connection in &config.linking.connections {
+            let from = instance_of(&connection.from)?;
+            let to = instance_of(&connection.to)?;
+
+            let (export, _, _) = graph
+                .get_component_of_instance(from)
+                .and_then(|(_, c)| c.export_by_name(&connection.export))
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Component '{}' has no export named '{}'",
+                        connection.from,
+                        connection.export
+                    )
+                })?;
+            let (import, _) = graph
+                .get_component_of_instance(to)
+                .and_then(|(_, c)| c.import_by_name(&connection.import))
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Component '{}' has no import named '{}'",
+                        connection.to,
+                        connection.import
+                    )
+                })?;
+
+            graph
+                .connect(from, Some(export), to, import)
+                .with_context(|| {
+                    format!(
+                        "Cannot connect {}.{} to {}.{}",
+                        connection.from, connection.export, connection.to, connection.import
+                    )
+                })?;
+            connected.insert((connection.to.clone(), connection.import.clone()));
+            used_exports.insert((connection.from.clone(), connection.export.clone()));
+        }
+
+        match config.linking.strategy.as_str() {
+            "manual" => self.report_unconnected(&graph, &instances, &connected)?,
+            "auto" => self.auto_link(&mut graph, &instances, &connected, used_exports)?,
+            other => {
+                return Err(anyhow!(
+                    "Unknown linking strategy '{}' (expected \"auto\" or \"manual\")",
+                    other
+                ))
             }
         }
 
-        Ok(analysis)
+        graph
+            .encode(EncodeOptions {
+                define_components: true,
+                export: Some(instances[0].1),
+                validate: true,
+            })
+            .map_err(|e| anyhow!("Composition failed: {:#}", e))
     }
 
-    fn manual_compose(
+    /// Connects every remaining import to the single component exporting the same
+    /// interface name, or fails with a report of what could not be linked.
+    ///
+    /// `used_exports` holds the (component, export) pairs the explicit
+    /// connections already use, so they are not reported as unused.
+    fn auto_link(
         &self,
-        components: &[ComponentAnalysis],
-        _config: &CompositionConfig,
-    ) -> Result<Vec<u8>> {
-        // This is a simplified manual composition
-        // In a real implementation, this would be much more complex
+        graph: &mut CompositionGraph,
+        instances: &[(&str, InstanceId)],
+        connected: &HashSet<(String, String)>,
+        mut used_exports: HashSet<(String, String)>,
+    ) -> Result<()> {
+        let mut exports: HashMap<String, Vec<usize>> = HashMap::new();
+        for (index, (name, instance)) in instances.iter().enumerate() {
+            let component = component_of(graph, name, *instance)?;
+            for (_, name, _, _) in component.exports() {
+                exports.entry(name.to_string()).or_default().push(index);
+            }
+        }
 
-        if components.is_empty() {
-            return Err(anyhow!("No components to compose"));
+        let mut report = LinkReport::default();
+        let mut links = Vec::new();
+
+        for (target, (target_name, instance)) in instances.iter().enumerate() {
+            let component = component_of(graph, target_name, *instance)?;
+
+            for (_, import, _) in component.imports() {
+                if connected.contains(&(target_name.to_string(), import.to_string())) {
+                    continue;
+                }
+
+                let providers: Vec<usize> = exports
+                    .get(import)
+                    .map(|p| p.iter().copied().filter(|&p| p != target).collect())
+                    .unwrap_or_default();
+
+                match providers.as_slice() {
+                    // Host interfaces are expected to stay imports of the composition
+                    [] if import.starts_with("wasi:") => {}
+                    [] => report
+                        .unsatisfied
+                        .push(format!("{target_name} imports '{import}'")),
+                    [source] => {
+                        used_exports.insert((instances[*source].0.to_string(), import.to_string()));
+                        links.push((*source, target, import.to_string()));
+                    }
+                    _ => report.ambiguous.push(format!(
+                        "'{}' imported by {} is exported by {}",
+                        import,
+                        target_name,
+                        providers
+                            .iter()
+                            .map(|&p| instances[p].0)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )),
+                }
+            }
+        }
+
+        // Exports of the root are re-exported, so only the others can go unused
+        for (name, providers) in &exports {
+            for &provider in providers {
+                let provider_name = instances[provider].0;
+                if provider != 0
+                    && !used_exports.contains(&(provider_name.to_string(), name.clone()))
+                {
+                    report
+                        .unused
+                        .push(format!("{provider_name} exports '{name}'"));
+                }
+            }
+        }
+        report.unused.sort();
+
+        if !report.unsatisfied.is_empty() || !report.ambiguous.is_empty() {
+            return Err(anyhow!(
+                "Could not link components automatically:\n{}\n\
+                 Add [[linking.connections]] entries or use strategy = \"manual\" to resolve.",
+                report
+            ));
         }
 
-        // For now, just return the first component as the composed result
-        // A full implementation would:
-        // 1. Analyze import/export compatibility
-        // 2. Resolve dependencies
-        // 3. Merge sections
-        // 4. Update indices
-        // 5. Generate new import/export sections
+        for (source, target, name) in links {
+            let source_instance = instances[source].1;
+            let target_instance = instances[target].1;
+            let (export, _, _) = component_of(graph, instances[source].0, source_instance)?
+                .export_by_name(&name)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Component '{}' has no export named '{}'",
+                        instances[source].0,
+                        name
+                    )
+                })?;
+            let (import, _) = component_of(graph, instances[target].0, target_instance)?
+                .import_by_name(&name)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Component '{}' has no import named '{}'",
+                        instances[target].0,
+                        name
+                    )
+                })?;
+
+            graph
+                .connect(source_instance, Some(export), target_instance, import)
+                .with_context(|| {
+                    format!(
+                        "Cannot connect {}.{} to {}.{}",
+                        instances[source].0, name, instances[target].0, name
+                    )
+                })?;
+        }
 
-        let first_component = std::fs::read(&components[0].path)?;
+        if !report.unused.is_empty() {
+            println!("⚠️  Unused exports:");
+            for unused in &report.unused {
+                println!("   - {unused}");
+            }
+        }
 
-        println!("📝 Manual composition is simplified - using first component as base");
-        println!("   For full composition features, install wasm-compose");
+        Ok(())
+    }
 
-        Ok(first_component)
+    /// Under manual linking, every import other than a host interface must
+    /// have a connection; fails with the list of those that do not.
+    fn report_unconnected(
+        &self,
+        graph: &CompositionGraph,
+        instances: &[(&str, InstanceId)],
+        connected: &HashSet<(String, String)>,
+    ) -> Result<()> {
+        let mut report = LinkReport::default();
+        for (name, instance) in instances {
+            for (_, import, _) in component_of(graph, name, *instance)?.imports() {
+                if !import.starts_with("wasi:")
+                    && !connected.contains(&(name.to_string(), import.to_string()))
+                {
+                    report
+                        .unsatisfied
+                        .push(format!("{name} imports '{import}'"));
+                }
+            }
+        }
+
+        if !report.unsatisfied.is_empty() {
+            return Err(anyhow!(
+                "Imports without a connection under manual linking:\n{}\n\
+                 Add [[linking.connections]] entries or use strategy = \"auto\" to resolve.",
+                report
+            ));
+        }
+        Ok(())
     }
 }
 
-#[derive(Debug)]
-#[allow(dead_code)]
-struct ComponentAnalysis {
-    path: PathBuf,
-    imports: Vec<ImportInfo>,
-    exports: Vec<ExportInfo>,
-    functions: Vec<FunctionInfo>,
+fn component_of<'a>(
+    graph: &'a CompositionGraph,
+    name: &str,
+    instance: InstanceId,
+) -> Result<&'a Component<'a>> {
+    graph
+        .get_component_of_instance(instance)
+        .map(|(_, component)| component)
+        .ok_or_else(|| anyhow!("Component '{}' is missing from the composition graph", name))
 }
 
-#[derive(Debug)]
-#[allow(dead_code)]
-struct ImportInfo {
-    module: String,
-    name: String,
-    ty: String,
+fn check_declared_interfaces(config: &ComponentConfig, component: &Component) -> Result<()> {
+    for export in &config.exports {
+        if component.export_by_name(export).is_none() {
+            return Err(anyhow!(
+                "Component '{}' does not export '{}'",
+                config.name,
+                export
+            ));
+        }
+    }
+
+    for import in &config.imports {
+        if component.import_by_name(import).is_none() {
+            return Err(anyhow!(
+                "Component '{}' does not import '{}'",
+                config.name,
+                import
+            ));
+        }
+    }
+
+    Ok(())
 }
 
-#[derive(Debug)]
-#[allow(dead_code)]
-struct ExportInfo {
-    name: String,
-    ty: String,
-    index: u32,
+#[derive(Debug, Default)]
+struct LinkReport {
+    unsatisfied: Vec<String>,
+    ambiguous: Vec<String>,
+    unused: Vec<String>,
 }
 
-#[derive(Debug)]
-#[allow(dead_code)]
-struct FunctionInfo {
-    name: String,
-    signature: String,
+impl std::fmt::Display for LinkReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sections = [
+            ("Unsatisfied imports", &self.unsatisfied),
+            ("Ambiguous providers", &self.ambiguous),
+            ("Unused exports", &self.unused),
+        ];
+
+        for (title, entries) in sections {
+            if entries.is_empty() {
+                continue;
+            }
+            writeln!(f, "  {title}:")?;
+            for entry in entries {
+                writeln!(f, "    - {entry}")?;
+            }
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,571 @@
+use crate::features::{feature_names, is_known_feature};
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Name of the project configuration file looked up in the project root.
+pub const CONFIG_FILE_NAME: &str = "wasm-wizard.toml";
+
+/// Compilation targets `build.target` may name.
+pub const SUPPORTED_TARGETS: &[&str] =
+    &["wasm32-wasip1", "wasm32-wasip2", "wasm32-unknown-unknown"];
+
+/// Build tools `build.tool` may name.
+pub const BUILD_TOOLS: &[&str] = &["cargo", "cargo-component", "wasm-pack", "npm"];
+
+/// Typed model of `wasm-wizard.toml`, shared by every command.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectConfig {
+    pub project: ProjectSection,
+    #[serde(default)]
+    pub build: BuildSection,
+    #[serde(default)]
+    pub optimize: OptimizeSection,
+    #[serde(default)]
+    pub dev: DevSection,
+    #[serde(default)]
+    pub composition: CompositionSection,
+    #[serde(default)]
+    pub toolchain: ToolchainSection,
+    #[serde(default)]
+    pub files: FilesSection,
+    #[serde(default, skip_serializing_if = "WorkspaceSection::is_empty")]
+    pub workspace: WorkspaceSection,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profile: BTreeMap<String, ProfileSection>,
+    #[serde(default, skip_serializing_if = "HooksSection::is_empty")]
+    pub hooks: HooksSection,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectSection {
+    pub name: String,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub template: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BuildSection {
+    /// Compilation target passed to the toolchain, one of [`SUPPORTED_TARGETS`]
+    pub target: String,
+    /// Build tool used to produce the component, one of [`BUILD_TOOLS`];
+    /// detected from the project's manifest when unset
+    pub tool: Option<String>,
+    /// Custom build command, run through the shell from the project root
+    pub command: Option<String>,
+    /// Optimize the output after every build
+    pub optimize: bool,
+    /// "component" wraps the compiled core module into a component, "module" keeps it as is
+    pub output_format: String,
+    /// WIT world embedded from `wit/` when the module carries none; needed
+    /// only when the package defines several worlds
+    pub world: Option<String>,
+    /// WASI preview1 adapter used instead of the bundled one
+    pub adapter: Option<String>,
+    /// Seconds the toolchain, build command and each hook may run before
+    /// the build is aborted
+    pub timeout: Option<u64>,
+}
+
+impl Default for BuildSection {
+    fn default() -> Self {
+        Self {
+            target: "wasm32-wasip1".to_string(),
+            tool: None,
+            command: None,
+            optimize: false,
+            output_format: "component".to_string(),
+            world: None,
+            adapter: None,
+            timeout: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OptimizeSection {
+    /// Optimization level (0-4)
+    pub level: u8,
+    /// Remove DWARF and other debug custom sections
+    pub strip_debug: bool,
+    /// Remove the `producers` custom section
+    pub strip_producers: bool,
+    /// Extra wasm-opt arguments, e.g. "-Oz" or "--converge"
+    pub passes: Vec<String>,
+    /// Proposals wasm-opt may use even when the input does not, by their
+    /// wasm-opt names, e.g. "simd" or "bulk-memory"
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub enable_features: Vec<String>,
+    /// Proposals wasm-opt must not use; inputs already using one are rejected
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub disable_features: Vec<String>,
+}
+
+impl Default for OptimizeSection {
+    fn default() -> Self {
+        Self {
+            level: 3,
+            strip_debug: true,
+            strip_producers: true,
+            passes: Vec::new(),
+            enable_features: Vec::new(),
+            disable_features: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DevSection {
+    pub port: u16,
+    pub host: String,
+    pub hot_reload: bool,
+    /// Glob-style paths, relative to the project root, that trigger a rebuild
+    pub watch_paths: Vec<String>,
+    /// External command used to serve the project instead of the built-in server
+    pub command: Option<String>,
+}
+
+impl Default for DevSection {
+    fn default() -> Self {
+        Self {
+            port: 8080,
+            host: "localhost".to_string(),
+            hot_reload: false,
+            watch_paths: vec!["src/**/*".to_string(), "wit/**/*".to_string()],
+            command: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CompositionSection {
+    /// "component" or "module"
+    pub output_format: String,
+    /// Optimize composed output
+    pub enable_optimization: bool,
+}
+
+impl Default for CompositionSection {
+    fn default() -> Self {
+        Self {
+            output_format: "component".to_string(),
+            enable_optimization: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ToolchainSection {
+    pub required_tools: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FilesSection {
+    /// Page served at `/` by the development server
+    pub entry: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkspaceSection {
+    /// Project directories, relative to the workspace root, handled by
+    /// `build`, `check`, `optimize` and `analyze`
+    pub members: Vec<String>,
+}
+
+impl WorkspaceSection {
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+}
+
+/// Shell commands `build` runs around its steps, from the project root.
+/// A failing command fails the build.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HooksSection {
+    /// Run before the toolchain builds the project
+    pub pre_build: Vec<String>,
+    /// Run for every toolchain output, before it is optimized or componentized
+    pub post_build: Vec<String>,
+    /// Run for every optimized module
+    pub post_optimize: Vec<String>,
+}
+
+impl HooksSection {
+    pub fn is_empty(&self) -> bool {
+        self.pre_build.is_empty() && self.post_build.is_empty() && self.post_optimize.is_empty()
+    }
+}
+
+/// Profiles available without a `[profile.<name>]` table.
+pub const BUILTIN_PROFILES: &[&str] = &["dev", "release", "size", "speed"];
+
+/// `[profile.<name>]`, selected with `build --profile <name>`. Unset fields
+/// keep the values of the built-in profile of that name, then those of
+/// `[build]` and `[optimize]`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProfileSection {
+    /// Cargo profile passed to `cargo build --profile`
+    pub cargo_profile: Option<String>,
+    /// Cargo features to enable
+    pub features: Option<Vec<String>>,
+    pub optimize: Option<bool>,
+    /// Optimization level (0-4)
+    pub level: Option<u8>,
+    /// Extra wasm-opt arguments, e.g. "-Oz" or "--converge"
+    pub passes: Option<Vec<String>>,
+    pub strip_debug: Option<bool>,
+    pub strip_producers: Option<bool>,
+    /// File name of the final artifact; `{name}` and `{profile}` are
+    /// replaced by the toolchain output's name and the profile name
+    pub output_name: Option<String>,
+}
+
+impl ProfileSection {
+    fn builtin(name: &str) -> Option<Self> {
+        let release = Self {
+            cargo_profile: Some("release".to_string()),
+            ..Default::default()
+        };
+
+        match name {
+            "dev" => Some(Self {
+                cargo_profile: Some("dev".to_string()),
+                optimize: Some(false),
+                ..Default::default()
+            }),
+            "release" => Some(release),
+            "size" => Some(Self {
+                optimize: Some(true),
+                level: Some(3),
+                passes: Some(vec!["-Oz".to_string(), "--converge".to_string()]),
+                strip_debug: Some(true),
+                strip_producers: Some(true),
+                ..release
+            }),
+            "speed" => Some(Self {
+                optimize: Some(true),
+                level: Some(4),
+                ..release
+            }),
+            _ => None,
+        }
+    }
+
+    /// Fields set in `self` win over those set in `base`.
+    fn or(self, base: Self) -> Self {
+        Self {
+            cargo_profile: self.cargo_profile.or(base.cargo_profile),
+            features: self.features.or(base.features),
+            optimize: self.optimize.or(base.optimize),
+            level: self.level.or(base.level),
+            passes: self.passes.or(base.passes),
+            strip_debug: self.strip_debug.or(base.strip_debug),
+            strip_producers: self.strip_producers.or(base.strip_producers),
+            output_name: self.output_name.or(base.output_name),
+        }
+    }
+}
+
+/// Toolchain settings of a resolved profile; the optimization settings are
+/// applied to the configuration returned by [`ProjectConfig::with_profile`].
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildProfile {
+    pub name: String,
+    pub cargo_profile: String,
+    pub features: Vec<String>,
+    pub output_name: Option<String>,
+}
+
+impl BuildProfile {
+    /// Final artifact file name for the toolchain output named `name`.
+    pub fn output_file_name(&self, name: &str) -> Option<String> {
+        self.output_name.as_ref().map(|pattern| {
+            pattern
+                .replace("{name}", name)
+                .replace("{profile}", &self.name)
+        })
+    }
+}
+
+impl ProjectConfig {
+    /// Loads the configuration of the project at `project_root`.
+    ///
+    /// Fails if the project has no `wasm-wizard.toml`.
+    pub fn load_project(project_root: &Path) -> Result<Self> {
+        let config_path = project_root.join(CONFIG_FILE_NAME);
+
+        if !config_path.exists() {
+            return Err(anyhow!(
+                "Not in a wasm-wizard project. Run 'wasm-wizard new' to create one."
+            ));
+        }
+
+        Self::load(&config_path)
+    }
+
+    /// Loads the project configuration if present, falling back to defaults.
+    pub fn load_or_default(project_root: &Path) -> Result<Self> {
+        let config_path = project_root.join(CONFIG_FILE_NAME);
+
+        if config_path.exists() {
+            Self::load(&config_path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&content, path)
+    }
+
+    /// Parses and validates configuration content; `path` is only used in error messages.
+    pub fn parse(content: &str, path: &Path) -> Result<Self> {
+        let config: Self = toml::from_str(content)
+            .map_err(|e| anyhow!("Invalid configuration in {}:\n{}", path.display(), e))?;
+
+        config
+            .validate()
+            .map_err(|e| anyhow!("Invalid configuration in {}: {}", path.display(), e))?;
+
+        Ok(config)
+    }
+
+    /// Whether `file_name` is the `output_name` of one of the `[profile]`
+    /// tables, i.e. an artifact `build` derived from a toolchain output.
+    pub fn is_profile_output(&self, file_name: &str) -> bool {
+        self.profile.iter().any(|(name, profile)| {
+            profile.output_name.as_ref().is_some_and(|pattern| {
+                let pattern = pattern.replace("{profile}", name);
+                // The toolchain output itself, renamed to what it already is
+                pattern != "{name}.wasm" && matches_output_name(&pattern, file_name)
+            })
+        })
+    }
+
+    /// Resolves the profile `name` and applies its optimization settings to
+    /// a copy of the configuration.
+    pub fn with_profile(&self, name: &str) -> Result<(Self, BuildProfile)> {
+        let builtin = ProfileSection::builtin(name);
+        let section = match (self.profile.get(name), builtin) {
+            (Some(section), Some(builtin)) => section.clone().or(builtin),
+            (Some(section), None) => section.clone(),
+            (None, Some(builtin)) => builtin,
+            (None, None) => {
+                let mut available: Vec<&str> = BUILTIN_PROFILES.to_vec();
+                available.extend(self.profile.keys().map(String::as_str));
+                available.sort();
+                available.dedup();
+                return Err(anyhow!(
+                    "Unknown profile \"{}\"; available profiles: {}",
+                    name,
+                    available.join(", ")
+                ));
+            }
+        };
+
+        let mut config = self.clone();
+        config.build.optimize = section.optimize.unwrap_or(config.build.optimize);
+        config.optimize.level = section.level.unwrap_or(config.optimize.level);
+        config.optimize.strip_debug = section.strip_debug.unwrap_or(config.optimize.strip_debug);
+        config.optimize.strip_producers = section
+            .strip_producers
+            .unwrap_or(config.optimize.strip_producers);
+        if let Some(passes) = section.passes {
+            config.optimize.passes = passes;
+        }
+
+        let profile = BuildProfile {
+            name: name.to_string(),
+            cargo_profile: section
+                .cargo_profile
+                .unwrap_or_else(|| "release".to_string()),
+            features: section.features.unwrap_or_default(),
+            output_name: section.output_name,
+        };
+
+        Ok((config, profile))
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.project.name.trim().is_empty() {
+            return Err(anyhow!("`project.name` cannot be empty"));
+        }
+
+        if self.optimize.level > 4 {
+            return Err(anyhow!(
+                "`optimize.level` must be between 0 and 4, found {}",
+                self.optimize.level
+            ));
+        }
+
+        for (key, features) in [
+            ("enable_features", &self.optimize.enable_features),
+            ("disable_features", &self.optimize.disable_features),
+        ] {
+            if let Some(unknown) = features.iter().find(|name| !is_known_feature(name)) {
+                return Err(anyhow!(
+                    "`optimize.{}` names unknown feature \"{}\"; known features are {}",
+                    key,
+                    unknown,
+                    feature_names()
+                ));
+            }
+        }
+        if let Some(both) = self
+            .optimize
+            .enable_features
+            .iter()
+            .find(|name| self.optimize.disable_features.contains(name))
+        {
+            return Err(anyhow!(
+                "Feature \"{}\" is listed in both `optimize.enable_features` and `optimize.disable_features`",
+                both
+            ));
+        }
+
+        if self.build.tool.is_some() && self.build.command.is_some() {
+            return Err(anyhow!(
+                "`build.tool` and `build.command` cannot both be set"
+            ));
+        }
+
+        match self.build.output_format.as_str() {
+            "component" | "module" => {}
+            other => {
+                return Err(anyhow!(
+                    "`build.output_format` must be \"component\" or \"module\", found \"{}\"",
+                    other
+                ))
+            }
+        }
+
+        if self.build.timeout == Some(0) {
+            return Err(anyhow!("`build.timeout` must be at least 1 second"));
+        }
+
+        if let Some(tool) = &self.build.tool {
+            // The package.json script runs componentize-js with the project's world
+            if tool == "componentize-js" {
+                return Err(anyhow!(
+                    "`build.tool = \"componentize-js\"` is not supported; use `tool = \"npm\"`, whose `build:wasm` script runs componentize-js"
+                ));
+            }
+            if !BUILD_TOOLS.contains(&tool.as_str()) {
+                return Err(anyhow!(
+                    "`build.tool` must be one of {}, found \"{}\"",
+                    BUILD_TOOLS.join(", "),
+                    tool
+                ));
+            }
+        }
+
+        if !SUPPORTED_TARGETS.contains(&self.build.target.as_str()) {
+            return Err(anyhow!(
+                "`build.target` must be one of {}, found \"{}\"",
+                SUPPORTED_TARGETS.join(", "),
+                self.build.target
+            ));
+        }
+
+        if self.build.target == "wasm32-wasip2" && self.build.output_format == "module" {
+            return Err(anyhow!(
+                "`build.target` \"wasm32-wasip2\" always produces components; set `build.output_format` to \"component\""
+            ));
+        }
+
+        match self.composition.output_format.as_str() {
+            "component" | "module" => {}
+            other => {
+                return Err(anyhow!(
+                    "`composition.output_format` must be \"component\" or \"module\", found \"{}\"",
+                    other
+                ))
+            }
+        }
+
+        for (name, profile) in &self.profile {
+            if let Some(level) = profile.level.filter(|&level| level > 4) {
+                return Err(anyhow!(
+                    "`profile.{}.level` must be between 0 and 4, found {}",
+                    name,
+                    level
+                ));
+            }
+            if let Some(output_name) = &profile.output_name {
+                if output_name.is_empty() || output_name.contains(['/', '\\']) {
+                    return Err(anyhow!(
+                        "`profile.{}.output_name` must be a file name, found \"{}\"",
+                        name,
+                        output_name
+                    ));
+                }
+            }
+        }
+
+        for (hook, commands) in [
+            ("pre_build", &self.hooks.pre_build),
+            ("post_build", &self.hooks.post_build),
+            ("post_optimize", &self.hooks.post_optimize),
+        ] {
+            if commands.iter().any(|command| command.trim().is_empty()) {
+                return Err(anyhow!("`hooks.{}` cannot contain empty commands", hook));
+            }
+        }
+
+        for (i, member) in self.workspace.members.iter().enumerate() {
+            if member.trim().is_empty() {
+                return Err(anyhow!("`workspace.members` cannot contain empty paths"));
+            }
+            if self.workspace.members[..i].contains(member) {
+                return Err(anyhow!(
+                    "`workspace.members` lists \"{}\" more than once",
+                    member
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether `file_name` is `pattern` with every `{name}` replaced by some
+/// non-empty text.
+fn matches_output_name(pattern: &str, file_name: &str) -> bool {
+    let mut literals = pattern.split("{name}");
+    let Some(mut rest) = file_name.strip_prefix(literals.next().unwrap_or_default()) else {
+        return false;
+    };
+    let literals: Vec<&str> = literals.collect();
+    let Some((last, middle)) = literals.split_last() else {
+        return rest.is_empty();
+    };
+
+    for literal in middle {
+        let skip = rest.chars().next().map_or(0, char::len_utf8);
+        let Some(index) = rest[skip..].find(literal) else {
+            return false;
+        };
+        rest = &rest[skip + index + literal.len()..];
+    }
+    rest.len() > last.len() && rest.ends_with(last)
+}
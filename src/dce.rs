@@ -0,0 +1,862 @@
+use crate::utils::{binary_reader, validate_wasm, write_uleb128};
+use anyhow::{anyhow, Result};
+use std::fmt;
+use std::ops::Range;
+use wasmparser::{
+    BlockType, CompositeType, ConstExpr, Data, DataKind, Element, ElementItems, ElementKind,
+    Export, ExternalKind, FunctionBody, Global, Import, Operator, OperatorsReader, Parser, Payload,
+    RefType, TableInit, TypeRef, ValType,
+};
+
+const HEADER_LEN: usize = 8;
+
+/// What [`eliminate_dead_code`] removed from a module.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Removed {
+    pub functions: usize,
+    pub globals: usize,
+    pub imports: usize,
+    pub types: usize,
+    /// Custom sections left out because they refer to code offsets
+    pub custom_sections: Vec<String>,
+}
+
+impl Removed {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl fmt::Display for Removed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} function(s), {} global(s), {} import(s) and {} type(s)",
+            self.functions, self.globals, self.imports, self.types
+        )
+    }
+}
+
+/// Removes the functions, globals, imports and types of a core module that
+/// nothing reachable from its exports, start function and element segments
+/// refers to, and renumbers the remaining ones.
+///
+/// Returns `None` when nothing can be removed. Modules using exception tags,
+/// GC types or typed function references are rejected: the analysis does not
+/// track the indices they refer to.
+pub fn eliminate_dead_code(wasm: &[u8]) -> Result<Option<(Vec<u8>, Removed)>> {
+    let module = Module::parse(wasm)?;
+    let live = module.live()?;
+    let remap = Remap::new(&live);
+
+    let mut removed = Removed {
+        functions: module.defined_funcs(&live.funcs),
+        globals: module.defined_globals(&live.globals),
+        imports: module
+            .imports
+            .iter()
+            .filter(|import| !import.kept(&remap))
+            .count(),
+        types: live.types.iter().filter(|&&live| !live).count(),
+        custom_sections: Vec::new(),
+    };
+    if removed.is_empty() {
+        return Ok(None);
+    }
+
+    let output = module.emit(&remap, &mut removed.custom_sections)?;
+    if validate_wasm(wasm).is_ok() {
+        validate_wasm(&output)
+            .map_err(|e| anyhow!("Dead code elimination produced an invalid module: {}", e))?;
+    }
+
+    Ok(Some((output, removed)))
+}
+
+/// The parts of a module the analysis reads, with the byte ranges needed to
+/// copy everything else unchanged.
+#[derive(Default)]
+struct Module<'a> {
+    wasm: &'a [u8],
+    types: Vec<Range<usize>>,
+    imports: Vec<ImportEntry<'a>>,
+    /// Type of every function, imported ones first
+    func_types: Vec<u32>,
+    imported_funcs: usize,
+    imported_globals: usize,
+    globals: Vec<Global<'a>>,
+    exports: Vec<Export<'a>>,
+    start: Option<u32>,
+    elements: Vec<Element<'a>>,
+    bodies: Vec<FunctionBody<'a>>,
+    data: Vec<Data<'a>>,
+}
+
+struct ImportEntry<'a> {
+    range: Range<usize>,
+    import: Import<'a>,
+    /// Index among the imports of the same kind, which is the function or
+    /// global index for function and global imports
+    index: u32,
+}
+
+/// Which functions, globals and types are reachable, by index.
+struct Live {
+    funcs: Vec<bool>,
+    globals: Vec<bool>,
+    types: Vec<bool>,
+}
+
+/// New indices of the live items; `None` for removed ones.
+struct Remap {
+    funcs: Vec<Option<u32>>,
+    globals: Vec<Option<u32>>,
+    types: Vec<Option<u32>>,
+}
+
+/// Items an expression or function body refers to.
+#[derive(Default)]
+struct Refs {
+    funcs: Vec<u32>,
+    globals: Vec<u32>,
+    types: Vec<u32>,
+}
+
+impl<'a> Module<'a> {
+    fn parse(wasm: &'a [u8]) -> Result<Self> {
+        let mut module = Module {
+            wasm,
+            ..Default::default()
+        };
+
+        for payload in Parser::new(0).parse_all(wasm) {
+            match payload? {
+                Payload::Version { encoding, .. } if encoding != wasmparser::Encoding::Module => {
+                    return Err(anyhow!("Only core modules are supported"));
+                }
+                Payload::TypeSection(reader) => {
+                    let end = reader.range().end;
+                    let mut starts = Vec::new();
+                    for group in reader.into_iter_with_offsets() {
+                        let (offset, group) = group?;
+                        let mut types = group.types();
+                        let (Some(ty), None) = (types.next(), types.next()) else {
+                            return Err(anyhow!("GC types are not supported"));
+                        };
+                        let CompositeType::Func(func) = &ty.composite_type else {
+                            return Err(anyhow!("GC types are not supported"));
+                        };
+                        if !ty.is_final || ty.supertype_idx.is_some() {
+                            return Err(anyhow!("GC types are not supported"));
+                        }
+                        for &ty in func.params().iter().chain(func.results()) {
+                            check_val_type(ty)?;
+                        }
+                        starts.push(offset);
+                    }
+                    let ends = starts.iter().skip(1).copied().chain([end]);
+                    module.types = starts.iter().zip(ends).map(|(&s, e)| s..e).collect();
+                }
+                Payload::ImportSection(reader) => {
+                    let end = reader.range().end;
+                    let mut imports = Vec::new();
+                    for import in reader.into_iter_with_offsets() {
+                        imports.push(import?);
+                    }
+                    let ends: Vec<usize> =
+                        imports.iter().skip(1).map(|i| i.0).chain([end]).collect();
+                    for ((start, import), end) in imports.into_iter().zip(ends) {
+                        let index = match import.ty {
+                            TypeRef::Func(ty) => {
+                                module.func_types.push(ty);
+                                module.imported_funcs += 1;
+                                module.imported_funcs - 1
+                            }
+                            TypeRef::Global(ty) => {
+                                check_val_type(ty.content_type)?;
+                                module.imported_globals += 1;
+                                module.imported_globals - 1
+                            }
+                            TypeRef::Table(ty) => {
+                                check_val_type(ValType::Ref(ty.element_type))?;
+                                0
+                            }
+                            TypeRef::Tag(_) => {
+                                return Err(anyhow!("Exception tags are not supported"))
+                            }
+                            TypeRef::Memory(_) => 0,
+                        };
+                        module.imports.push(ImportEntry {
+                            range: start..end,
+                            import,
+                            index: index as u32,
+                        });
+                    }
+                }
+                Payload::FunctionSection(reader) => {
+                    for ty in reader {
+                        module.func_types.push(ty?);
+                    }
+                }
+                Payload::TableSection(reader) => {
+                    for table in reader {
+                        let table = table?;
+                        check_val_type(ValType::Ref(table.ty.element_type))?;
+                        if !matches!(table.init, TableInit::RefNull) {
+                            return Err(anyhow!("Table initializers are not supported"));
+                        }
+                    }
+                }
+                Payload::TagSection(_) => {
+                    return Err(anyhow!("Exception tags are not supported"));
+                }
+                Payload::GlobalSection(reader) => {
+                    for global in reader {
+                        let global = global?;
+                        check_val_type(global.ty.content_type)?;
+                        module.globals.push(global);
+                    }
+                }
+                Payload::ExportSection(reader) => {
+                    for export in reader {
+                        module.exports.push(export?);
+                    }
+                }
+                Payload::StartSection { func, .. } => module.start = Some(func),
+                Payload::ElementSection(reader) => {
+                    for element in reader {
+                        let element = element?;
+                        if let ElementItems::Expressions(ty, _) = &element.items {
+                            check_val_type(ValType::Ref(*ty))?;
+                        }
+                        module.elements.push(element);
+                    }
+                }
+                Payload::DataSection(reader) => {
+                    for data in reader {
+                        module.data.push(data?);
+                    }
+                }
+                Payload::CodeSectionEntry(body) => {
+                    for local in body.get_locals_reader()? {
+                        check_val_type(local?.1)?;
+                    }
+                    module.bodies.push(body);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(module)
+    }
+
+    /// Walks everything reachable from the exports, the start function and
+    /// the element and data segments.
+    fn live(&self) -> Result<Live> {
+        let mut live = Live {
+            funcs: vec![false; self.func_types.len()],
+            globals: vec![false; self.imported_globals + self.globals.len()],
+            types: vec![false; self.types.len()],
+        };
+
+        let mut roots = Refs::default();
+        for export in &self.exports {
+            match export.kind {
+                ExternalKind::Func => roots.funcs.push(export.index),
+                ExternalKind::Global => roots.globals.push(export.index),
+                _ => {}
+            }
+        }
+        roots.funcs.extend(self.start);
+        for element in &self.elements {
+            if let ElementKind::Active { offset_expr, .. } = &element.kind {
+                scan(offset_expr.get_operators_reader(), &mut roots)?;
+            }
+            match &element.items {
+                ElementItems::Functions(funcs) => {
+                    for func in funcs.clone() {
+                        roots.funcs.push(func?);
+                    }
+                }
+                ElementItems::Expressions(_, exprs) => {
+                    for expr in exprs.clone() {
+                        scan(expr?.get_operators_reader(), &mut roots)?;
+                    }
+                }
+            }
+        }
+        for data in &self.data {
+            if let DataKind::Active { offset_expr, .. } = &data.kind {
+                scan(offset_expr.get_operators_reader(), &mut roots)?;
+            }
+        }
+
+        let mut pending = roots;
+        loop {
+            let mut refs = Refs::default();
+            for func in pending.funcs.drain(..) {
+                let index = func as usize;
+                if index >= live.funcs.len() {
+                    return Err(anyhow!("Function index {} out of bounds", func));
+                }
+                if std::mem::replace(&mut live.funcs[index], true) {
+                    continue;
+                }
+                refs.types.push(self.func_types[index]);
+                if let Some(body) = index.checked_sub(self.imported_funcs) {
+                    let body = self.bodies.get(body).ok_or_else(|| {
+                        anyhow!("Function {} has no body in the code section", func)
+                    })?;
+                    scan(body.get_operators_reader()?, &mut refs)?;
+                }
+            }
+            for global in pending.globals.drain(..) {
+                let index = global as usize;
+                if index >= live.globals.len() {
+                    return Err(anyhow!("Global index {} out of bounds", global));
+                }
+                if std::mem::replace(&mut live.globals[index], true) {
+                    continue;
+                }
+                if let Some(defined) = index.checked_sub(self.imported_globals) {
+                    scan(
+                        self.globals[defined].init_expr.get_operators_reader(),
+                        &mut refs,
+                    )?;
+                }
+            }
+            for ty in pending.types.drain(..) {
+                let ty = live
+                    .types
+                    .get_mut(ty as usize)
+                    .ok_or_else(|| anyhow!("Type index {} out of bounds", ty))?;
+                *ty = true;
+            }
+
+            if refs.funcs.is_empty() && refs.globals.is_empty() && refs.types.is_empty() {
+                return Ok(live);
+            }
+            pending = refs;
+        }
+    }
+
+    fn defined_funcs(&self, live: &[bool]) -> usize {
+        live[self.imported_funcs..].iter().filter(|&&l| !l).count()
+    }
+
+    fn defined_globals(&self, live: &[bool]) -> usize {
+        live[self.imported_globals..]
+            .iter()
+            .filter(|&&l| !l)
+            .count()
+    }
+
+    /// Re-encodes the module without the dead items. Sections the analysis
+    /// does not touch are copied byte for byte and the name section is
+    /// renumbered; DWARF sections refer to code offsets that moved, so they
+    /// are left out and added to `dropped`.
+    fn emit(&self, remap: &Remap, dropped: &mut Vec<String>) -> Result<Vec<u8>> {
+        const CUSTOM: u8 = 0;
+        const TYPE: u8 = 1;
+        const IMPORT: u8 = 2;
+        const FUNCTION: u8 = 3;
+        const GLOBAL: u8 = 6;
+        const EXPORT: u8 = 7;
+        const START: u8 = 8;
+        const ELEMENT: u8 = 9;
+        const CODE: u8 = 10;
+        const DATA: u8 = 11;
+
+        let wasm = self.wasm;
+        let mut output = wasm[..HEADER_LEN].to_vec();
+        let mut reader = binary_reader(&wasm[HEADER_LEN..]);
+        while !reader.eof() {
+            let start = HEADER_LEN + reader.current_position();
+            let id = reader.read_u8()?;
+            let size = reader.read_var_u32()? as usize;
+            let contents = reader.read_bytes(size)?;
+            let end = HEADER_LEN + reader.current_position();
+
+            let mut section = Section::default();
+            match id {
+                CUSTOM => {
+                    let name = binary_reader(contents).read_string()?;
+                    if name == "name" {
+                        let names = rewrite_names(contents, remap)?;
+                        output.push(CUSTOM);
+                        write_uleb128(&mut output, names.len() as u64);
+                        output.extend_from_slice(&names);
+                    } else if name.starts_with(".debug") {
+                        dropped.push(name.to_string());
+                    } else {
+                        output.extend_from_slice(&wasm[start..end]);
+                    }
+                    continue;
+                }
+                TYPE => {
+                    for (ty, range) in self.types.iter().enumerate() {
+                        if remap.types[ty].is_some() {
+                            section.raw(&wasm[range.clone()]);
+                        }
+                    }
+                }
+                IMPORT => {
+                    for entry in self.imports.iter().filter(|entry| entry.kept(remap)) {
+                        let import = &entry.import;
+                        match import.ty {
+                            TypeRef::Func(ty) => {
+                                let bytes = section.item();
+                                write_name(bytes, import.module);
+                                write_name(bytes, import.name);
+                                bytes.push(0x00);
+                                write_uleb128(bytes, remap.ty(ty)? as u64);
+                            }
+                            _ => section.raw(&wasm[entry.range.clone()]),
+                        }
+                    }
+                }
+                FUNCTION => {
+                    for (func, &ty) in self.func_types.iter().enumerate().skip(self.imported_funcs)
+                    {
+                        if remap.funcs[func].is_some() {
+                            write_uleb128(section.item(), remap.ty(ty)? as u64);
+                        }
+                    }
+                }
+                GLOBAL => {
+                    for (defined, global) in self.globals.iter().enumerate() {
+                        if remap.globals[self.imported_globals + defined].is_none() {
+                            continue;
+                        }
+                        let expr = global.init_expr.get_binary_reader().original_position();
+                        let type_start = global_type_start(global, expr)?;
+                        let bytes = section.item();
+                        bytes.extend_from_slice(&wasm[type_start..expr]);
+                        self.rewrite_expr(&global.init_expr, remap, bytes)?;
+                    }
+                }
+                EXPORT => {
+                    for export in &self.exports {
+                        let index = match export.kind {
+                            ExternalKind::Func => remap.func(export.index)?,
+                            ExternalKind::Global => remap.global(export.index)?,
+                            _ => export.index,
+                        };
+                        let bytes = section.item();
+                        write_name(bytes, export.name);
+                        bytes.push(external_kind_byte(export.kind));
+                        write_uleb128(bytes, index as u64);
+                    }
+                }
+                START => {
+                    let func = self
+                        .start
+                        .ok_or_else(|| anyhow!("Missing start function"))?;
+                    output.push(START);
+                    let mut bytes = Vec::new();
+                    write_uleb128(&mut bytes, remap.func(func)? as u64);
+                    write_uleb128(&mut output, bytes.len() as u64);
+                    output.extend_from_slice(&bytes);
+                    continue;
+                }
+                ELEMENT => {
+                    for element in &self.elements {
+                        self.rewrite_element(element, remap, section.item())?;
+                    }
+                }
+                CODE => {
+                    for (defined, body) in self.bodies.iter().enumerate() {
+                        if remap.funcs[self.imported_funcs + defined].is_none() {
+                            continue;
+                        }
+                        let operators = body.get_operators_reader()?;
+                        let mut code =
+                            wasm[body.range().start..operators.original_position()].to_vec();
+                        self.rewrite_operators(operators, remap, &mut code)?;
+                        let bytes = section.item();
+                        write_uleb128(bytes, code.len() as u64);
+                        bytes.extend_from_slice(&code);
+                    }
+                }
+                DATA => {
+                    for data in &self.data {
+                        let bytes = section.item();
+                        match &data.kind {
+                            DataKind::Passive => bytes.push(0x01),
+                            DataKind::Active {
+                                memory_index: 0,
+                                offset_expr,
+                            } => {
+                                bytes.push(0x00);
+                                self.rewrite_expr(offset_expr, remap, bytes)?;
+                            }
+                            DataKind::Active {
+                                memory_index,
+                                offset_expr,
+                            } => {
+                                bytes.push(0x02);
+                                write_uleb128(bytes, *memory_index as u64);
+                                self.rewrite_expr(offset_expr, remap, bytes)?;
+                            }
+                        }
+                        write_uleb128(bytes, data.data.len() as u64);
+                        bytes.extend_from_slice(data.data);
+                    }
+                }
+                _ => {
+                    output.extend_from_slice(&wasm[start..end]);
+                    continue;
+                }
+            }
+
+            // Dropping the last function leaves empty sections behind
+            if section.count > 0 || matches!(id, EXPORT | ELEMENT | DATA) {
+                section.finish(id, &mut output);
+            }
+        }
+
+        Ok(output)
+    }
+
+    fn rewrite_element(&self, element: &Element, remap: &Remap, bytes: &mut Vec<u8>) -> Result<()> {
+        let expressions = matches!(element.items, ElementItems::Expressions(..));
+        let (mut flags, table, offset) = match &element.kind {
+            ElementKind::Passive => (0x01, None, None),
+            ElementKind::Declared => (0x03, None, None),
+            ElementKind::Active {
+                table_index,
+                offset_expr,
+            } => (
+                if table_index.is_some() { 0x02 } else { 0x00 },
+                *table_index,
+                Some(offset_expr),
+            ),
+        };
+        if expressions {
+            flags |= 0x04;
+        }
+
+        write_uleb128(bytes, flags as u64);
+        if let Some(table) = table {
+            write_uleb128(bytes, table as u64);
+        }
+        if let Some(offset) = offset {
+            self.rewrite_expr(offset, remap, bytes)?;
+        }
+        // Flag 0 and 4 segments imply the element kind or type
+        let explicit_type = flags & 0x03 != 0;
+
+        match &element.items {
+            ElementItems::Functions(funcs) => {
+                if explicit_type {
+                    bytes.push(0x00);
+                }
+                write_uleb128(bytes, funcs.count() as u64);
+                for func in funcs.clone() {
+                    write_uleb128(bytes, remap.func(func?)? as u64);
+                }
+            }
+            ElementItems::Expressions(ty, exprs) => {
+                if explicit_type {
+                    bytes.push(match *ty {
+                        RefType::FUNCREF => 0x70,
+                        RefType::EXTERNREF => 0x6f,
+                        _ => return Err(anyhow!("Unsupported element type {:?}", ty)),
+                    });
+                }
+                write_uleb128(bytes, exprs.count() as u64);
+                for expr in exprs.clone() {
+                    self.rewrite_expr(&expr?, remap, bytes)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn rewrite_expr(&self, expr: &ConstExpr, remap: &Remap, bytes: &mut Vec<u8>) -> Result<()> {
+        self.rewrite_operators(expr.get_operators_reader(), remap, bytes)
+    }
+
+    /// Copies the operators left in `reader`, renumbering the functions,
+    /// globals and types they refer to.
+    fn rewrite_operators(
+        &self,
+        mut reader: OperatorsReader,
+        remap: &Remap,
+        bytes: &mut Vec<u8>,
+    ) -> Result<()> {
+        while !reader.eof() {
+            let start = reader.original_position();
+            let operator = reader.read()?;
+            let raw = &self.wasm[start..reader.original_position()];
+
+            let (opcode, indices) = match operator {
+                Operator::Call { function_index } => (0x10, [remap.func(function_index)?, 0]),
+                Operator::ReturnCall { function_index } => (0x12, [remap.func(function_index)?, 0]),
+                Operator::RefFunc { function_index } => (0xd2, [remap.func(function_index)?, 0]),
+                Operator::GlobalGet { global_index } => (0x23, [remap.global(global_index)?, 0]),
+                Operator::GlobalSet { global_index } => (0x24, [remap.global(global_index)?, 0]),
+                Operator::CallIndirect {
+                    type_index,
+                    table_index,
+                    ..
+                } => (0x11, [remap.ty(type_index)?, table_index]),
+                Operator::ReturnCallIndirect {
+                    type_index,
+                    table_index,
+                } => (0x13, [remap.ty(type_index)?, table_index]),
+                Operator::Block {
+                    blockty: BlockType::FuncType(ty),
+                }
+                | Operator::Loop {
+                    blockty: BlockType::FuncType(ty),
+                }
+                | Operator::If {
+                    blockty: BlockType::FuncType(ty),
+                }
+                | Operator::Try {
+                    blockty: BlockType::FuncType(ty),
+                } => {
+                    bytes.push(raw[0]);
+                    write_sleb128(bytes, remap.ty(ty)? as i64);
+                    continue;
+                }
+                _ => {
+                    bytes.extend_from_slice(raw);
+                    continue;
+                }
+            };
+
+            bytes.push(opcode);
+            write_uleb128(bytes, indices[0] as u64);
+            if matches!(opcode, 0x11 | 0x13) {
+                write_uleb128(bytes, indices[1] as u64);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ImportEntry<'_> {
+    fn kept(&self, remap: &Remap) -> bool {
+        match self.import.ty {
+            TypeRef::Func(_) => remap.funcs[self.index as usize].is_some(),
+            TypeRef::Global(_) => remap.globals[self.index as usize].is_some(),
+            _ => true,
+        }
+    }
+}
+
+impl Remap {
+    fn new(live: &Live) -> Self {
+        fn renumber(live: &[bool]) -> Vec<Option<u32>> {
+            let mut next = 0;
+            live.iter()
+                .map(|&live| {
+                    live.then(|| {
+                        next += 1;
+                        next - 1
+                    })
+                })
+                .collect()
+        }
+
+        Self {
+            funcs: renumber(&live.funcs),
+            globals: renumber(&live.globals),
+            types: renumber(&live.types),
+        }
+    }
+
+    fn func(&self, index: u32) -> Result<u32> {
+        lookup(&self.funcs, index, "Function")
+    }
+
+    fn global(&self, index: u32) -> Result<u32> {
+        lookup(&self.globals, index, "Global")
+    }
+
+    fn ty(&self, index: u32) -> Result<u32> {
+        lookup(&self.types, index, "Type")
+    }
+}
+
+fn lookup(indices: &[Option<u32>], index: u32, kind: &str) -> Result<u32> {
+    indices
+        .get(index as usize)
+        .copied()
+        .flatten()
+        .ok_or_else(|| anyhow!("{} {} was removed but is still referenced", kind, index))
+}
+
+/// Renumbers the functions, globals and types the `name` section `contents`
+/// refers to and leaves out the names of removed ones. Subsections naming
+/// items the analysis does not touch are copied byte for byte.
+fn rewrite_names(contents: &[u8], remap: &Remap) -> Result<Vec<u8>> {
+    const FUNCTION: u8 = 1;
+    const LOCAL: u8 = 2;
+    const LABEL: u8 = 3;
+    const TYPE: u8 = 4;
+    const GLOBAL: u8 = 7;
+    const FIELD: u8 = 10;
+
+    let mut reader = binary_reader(contents);
+    reader.read_string()?;
+    let mut output = contents[..reader.current_position()].to_vec();
+
+    while !reader.eof() {
+        let start = reader.current_position();
+        let id = reader.read_u8()?;
+        let size = reader.read_var_u32()? as usize;
+        let subsection = reader.read_bytes(size)?;
+
+        // Local, label and field names are name maps per function or type
+        let (indices, indirect) = match id {
+            FUNCTION => (&remap.funcs, false),
+            LOCAL | LABEL => (&remap.funcs, true),
+            TYPE => (&remap.types, false),
+            GLOBAL => (&remap.globals, false),
+            FIELD => (&remap.types, true),
+            _ => {
+                output.extend_from_slice(&contents[start..reader.current_position()]);
+                continue;
+            }
+        };
+
+        let mut entries = binary_reader(subsection);
+        let mut section = Section::default();
+        for _ in 0..entries.read_var_u32()? {
+            let index = entries.read_var_u32()?;
+            let entry_start = entries.current_position();
+            if indirect {
+                for _ in 0..entries.read_var_u32()? {
+                    entries.read_var_u32()?;
+                    entries.read_string()?;
+                }
+            } else {
+                entries.read_string()?;
+            }
+
+            if let Some(&Some(index)) = indices.get(index as usize) {
+                let bytes = section.item();
+                write_uleb128(bytes, index as u64);
+                bytes.extend_from_slice(&subsection[entry_start..entries.current_position()]);
+            }
+        }
+        section.finish(id, &mut output);
+    }
+
+    Ok(output)
+}
+
+/// Collects the items the operators left in `reader` refer to.
+fn scan(mut reader: OperatorsReader, refs: &mut Refs) -> Result<()> {
+    while !reader.eof() {
+        match reader.read()? {
+            Operator::Call { function_index }
+            | Operator::ReturnCall { function_index }
+            | Operator::RefFunc { function_index } => refs.funcs.push(function_index),
+            Operator::GlobalGet { global_index } | Operator::GlobalSet { global_index } => {
+                refs.globals.push(global_index)
+            }
+            Operator::CallIndirect { type_index, .. }
+            | Operator::ReturnCallIndirect { type_index, .. } => refs.types.push(type_index),
+            Operator::Block { blockty }
+            | Operator::Loop { blockty }
+            | Operator::If { blockty }
+            | Operator::Try { blockty } => match blockty {
+                BlockType::FuncType(ty) => refs.types.push(ty),
+                BlockType::Type(ty) => check_val_type(ty)?,
+                BlockType::Empty => {}
+            },
+            Operator::TypedSelect { ty } => check_val_type(ty)?,
+            Operator::RefNull { hty } => check_val_type(ValType::Ref(
+                RefType::new(true, hty).ok_or_else(|| anyhow!("Unsupported heap type"))?,
+            ))?,
+            Operator::CallRef { .. } | Operator::ReturnCallRef { .. } => {
+                return Err(anyhow!("Typed function references are not supported"))
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn check_val_type(ty: ValType) -> Result<()> {
+    match ty {
+        ValType::Ref(ty) if ty.type_index().is_some() => {
+            Err(anyhow!("Typed function references are not supported"))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Globals are a value type and a mutability byte followed by the init
+/// expression; without GC types the value type is a single byte.
+fn global_type_start(global: &Global, expr: usize) -> Result<usize> {
+    match global.ty.content_type {
+        ValType::Ref(ty) if !matches!(ty, RefType::FUNCREF | RefType::EXTERNREF) => Err(anyhow!(
+            "Unsupported global type {:?}",
+            global.ty.content_type
+        )),
+        _ => Ok(expr - 2),
+    }
+}
+
+fn external_kind_byte(kind: ExternalKind) -> u8 {
+    match kind {
+        ExternalKind::Func => 0x00,
+        ExternalKind::Table => 0x01,
+        ExternalKind::Memory => 0x02,
+        ExternalKind::Global => 0x03,
+        ExternalKind::Tag => 0x04,
+    }
+}
+
+fn write_name(bytes: &mut Vec<u8>, name: &str) {
+    write_uleb128(bytes, name.len() as u64);
+    bytes.extend_from_slice(name.as_bytes());
+}
+
+fn write_sleb128(bytes: &mut Vec<u8>, mut value: i64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0) {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// A vector section being re-encoded.
+#[derive(Default)]
+struct Section {
+    count: u32,
+    bytes: Vec<u8>,
+}
+
+impl Section {
+    /// Starts the next entry; returns the buffer to write it to.
+    fn item(&mut self) -> &mut Vec<u8> {
+        self.count += 1;
+        &mut self.bytes
+    }
+
+    fn raw(&mut self, entry: &[u8]) {
+        self.item().extend_from_slice(entry);
+    }
+
+    fn finish(self, id: u8, output: &mut Vec<u8>) {
+        let mut contents = Vec::new();
+        write_uleb128(&mut contents, self.count as u64);
+        contents.extend_from_slice(&self.bytes);
+        output.push(id);
+        write_uleb128(output, contents.len() as u64);
+        output.extend_from_slice(&contents);
+    }
+}
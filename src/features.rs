@@ -0,0 +1,78 @@
+use crate::utils::validation_features;
+use anyhow::{anyhow, Result};
+use wasmparser::{Validator, WasmFeatures};
+
+/// A post-MVP proposal wasm-opt can be told to use, by its wasm-opt name.
+struct Feature {
+    name: &'static str,
+    /// Validator flags of the proposal
+    flags: WasmFeatures,
+}
+
+/// Proposals the optimizer detects and passes to wasm-opt.
+const FEATURES: &[Feature] = &[
+    Feature::new("sign-ext", WasmFeatures::SIGN_EXTENSION),
+    Feature::new("mutable-globals", WasmFeatures::MUTABLE_GLOBAL),
+    Feature::new(
+        "nontrapping-float-to-int",
+        WasmFeatures::SATURATING_FLOAT_TO_INT,
+    ),
+    Feature::new("multivalue", WasmFeatures::MULTI_VALUE),
+    Feature::new("bulk-memory", WasmFeatures::BULK_MEMORY),
+    Feature::new("reference-types", WasmFeatures::REFERENCE_TYPES),
+    Feature::new("simd", WasmFeatures::SIMD),
+    Feature::new("relaxed-simd", WasmFeatures::RELAXED_SIMD),
+    Feature::new("threads", WasmFeatures::THREADS),
+    Feature::new("tail-call", WasmFeatures::TAIL_CALL),
+    Feature::new("exception-handling", WasmFeatures::EXCEPTIONS),
+    Feature::new("memory64", WasmFeatures::MEMORY64),
+    Feature::new("multimemory", WasmFeatures::MULTI_MEMORY),
+    Feature::new("extended-const", WasmFeatures::EXTENDED_CONST),
+    // wasm-opt folds typed function references into GC
+    Feature::new(
+        "gc",
+        WasmFeatures::GC.union(WasmFeatures::FUNCTION_REFERENCES),
+    ),
+];
+
+impl Feature {
+    const fn new(name: &'static str, flags: WasmFeatures) -> Self {
+        Self { name, flags }
+    }
+}
+
+pub fn is_known_feature(name: &str) -> bool {
+    FEATURES.iter().any(|feature| feature.name == name)
+}
+
+/// Names of all [`FEATURES`], for error messages.
+pub fn feature_names() -> String {
+    FEATURES
+        .iter()
+        .map(|feature| feature.name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Names of the proposals `wasm` uses, in the order of [`FEATURES`].
+///
+/// The validator accepts the input with every proposal enabled; a proposal
+/// is in use when the input no longer validates without it.
+pub fn detect_features(wasm: &[u8]) -> Result<Vec<&'static str>> {
+    let all = FEATURES
+        .iter()
+        .fold(validation_features(), |all, feature| all | feature.flags);
+    Validator::new_with_features(all)
+        .validate_all(wasm)
+        .map_err(|e| anyhow!("the input does not validate: {}", e))?;
+
+    Ok(FEATURES
+        .iter()
+        .filter(|feature| {
+            Validator::new_with_features(all - feature.flags)
+                .validate_all(wasm)
+                .is_err()
+        })
+        .map(|feature| feature.name)
+        .collect())
+}
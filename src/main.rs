@@ -2,11 +2,21 @@ use anyhow::Result;
 use clap::{Parser, Subcommand};
 use colored::*;
 
+mod analyzer;
+mod builder;
+mod cache;
 mod commands;
+mod componentizer;
 mod composer;
+mod config;
+mod dce;
+mod features;
 mod optimizer;
 mod scaffolder;
+mod server;
 mod utils;
+mod watcher;
+mod workspace;
 
 use commands::*;
 
@@ -58,19 +68,26 @@ enum Commands {
 
     /// Analyze component size and performance
     Analyze(AnalyzeCommand),
+
+    /// Manage the build and optimization cache
+    Cache(CacheCommand),
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
-
-    // Show wizard banner
-    println!("{}", "🧙‍♂️ WASM Wizard".bright_magenta().bold());
-    println!(
-        "{}",
-        "Making WebAssembly Component Model ridiculously easy!".bright_cyan()
-    );
-    println!();
+    utils::listen_for_ctrl_c();
+
+    // Show wizard banner, unless stdout is meant for other programs
+    let machine_readable = matches!(&cli.command, Commands::Analyze(cmd) if cmd.format == "json");
+    if !machine_readable {
+        println!("{}", "🧙‍♂️ WASM Wizard".bright_magenta().bold());
+        println!(
+            "{}",
+            "Making WebAssembly Component Model ridiculously easy!".bright_cyan()
+        );
+        println!();
+    }
 
     match cli.command {
         Commands::New(cmd) => cmd.execute().await,
@@ -83,5 +100,6 @@ async fn main() -> Result<()> {
         Commands::Dev(cmd) => cmd.execute().await,
         Commands::Bindings(cmd) => cmd.execute().await,
         Commands::Analyze(cmd) => cmd.execute().await,
+        Commands::Cache(cmd) => cmd.execute().await,
     }
 }
@@ -1,21 +1,195 @@
-use anyhow::{anyhow, Result};
+use crate::analyzer::nested_modules;
+use crate::cache::{Cache, CacheKey};
+use crate::config::OptimizeSection;
+use crate::dce::eliminate_dead_code;
+use crate::features::detect_features;
+use crate::utils::{
+    binary_reader, format_size, print_table, run_command, run_command_output, validate_wasm,
+    write_uleb128,
+};
+use anyhow::{anyhow, Context, Result};
+use colored::*;
 use std::path::Path;
-use std::process::Command;
 
+#[derive(Clone)]
 pub struct Optimizer {
     wasm_opt_path: Option<String>,
+    strip_debug: bool,
+    strip_producers: bool,
+    /// Also drop the name section, which the manual optimizer keeps otherwise
+    strip_names: bool,
+    passes: Vec<String>,
+    enable_features: Vec<String>,
+    disable_features: Vec<String>,
+    cache: Option<Cache>,
+}
+
+/// One rung of the ladder `optimize --size` climbs, from the cheapest
+/// optimization to the most aggressive one.
+pub struct Strategy {
+    pub name: &'static str,
+    pub level: u8,
+    /// wasm-opt passes replacing the configured ones
+    passes: &'static [&'static str],
+    /// Strip debug information, names and producers regardless of the
+    /// configuration
+    strip_all: bool,
+}
+
+const WASM_OPT_STRATEGIES: &[Strategy] = &[
+    Strategy::new("-O1", 1, &[], false),
+    Strategy::new("-O2", 2, &[], false),
+    Strategy::new("-O3", 3, &[], false),
+    Strategy::new("-Os", 3, &["-Os"], false),
+    Strategy::new("-Oz", 3, &["-Oz"], false),
+    Strategy::new("-Oz, strip names and producers", 3, &["-Oz"], true),
+    Strategy::new(
+        "-Oz --converge, strip names and producers",
+        3,
+        &["-Oz", "--converge"],
+        true,
+    ),
+];
+
+const MANUAL_STRATEGIES: &[Strategy] = &[
+    Strategy::new("level 1: dead code elimination", 1, &[], false),
+    Strategy::new(
+        "level 2: strip non-essential custom sections",
+        2,
+        &[],
+        false,
+    ),
+    Strategy::new("level 2, strip names and producers", 2, &[], true),
+];
+
+impl Strategy {
+    const fn new(
+        name: &'static str,
+        level: u8,
+        passes: &'static [&'static str],
+        strip_all: bool,
+    ) -> Self {
+        Self {
+            name,
+            level,
+            passes,
+            strip_all,
+        }
+    }
 }
 
 impl Optimizer {
-    pub fn new() -> Self {
+    pub fn with_config(config: &OptimizeSection) -> Self {
         Self {
             wasm_opt_path: which::which("wasm-opt")
                 .ok()
                 .map(|p| p.to_string_lossy().to_string()),
+            strip_debug: config.strip_debug,
+            strip_producers: config.strip_producers,
+            strip_names: false,
+            passes: config.passes.clone(),
+            enable_features: config.enable_features.clone(),
+            disable_features: config.disable_features.clone(),
+            cache: None,
+        }
+    }
+
+    /// Reuses earlier results for identical inputs and settings.
+    pub fn with_cache(mut self, cache: Option<Cache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    pub fn has_wasm_opt(&self) -> bool {
+        self.wasm_opt_path.is_some()
+    }
+
+    /// Strategies for `optimize --size`, in the order they are tried.
+    pub fn strategies(&self) -> &'static [Strategy] {
+        if self.wasm_opt_path.is_some() {
+            WASM_OPT_STRATEGIES
+        } else {
+            MANUAL_STRATEGIES
+        }
+    }
+
+    /// This optimizer with the passes and strip settings of `strategy`;
+    /// optimize at `strategy.level` to apply it.
+    pub fn with_strategy(&self, strategy: &Strategy) -> Self {
+        let mut optimizer = self.clone();
+        optimizer.passes = strategy
+            .passes
+            .iter()
+            .map(|pass| pass.to_string())
+            .collect();
+        if strategy.strip_all {
+            optimizer.strip_debug = true;
+            optimizer.strip_producers = true;
+            optimizer.strip_names = true;
+        }
+        optimizer
+    }
+
+    /// Identifies the optimizer in cache keys, so installing or upgrading
+    /// wasm-opt invalidates earlier results.
+    pub async fn version(&self) -> String {
+        let Some(wasm_opt) = &self.wasm_opt_path else {
+            return "manual".to_string();
+        };
+
+        match run_command_output(wasm_opt, &["--version"], None).await {
+            Ok(version) => version.trim().to_string(),
+            Err(_) => wasm_opt.clone(),
         }
     }
 
     pub async fn optimize_file(&self, input: &Path, output: &Path, level: u8) -> Result<()> {
+        let key = match &self.cache {
+            Some(_) => Some(
+                CacheKey::new("optimize")
+                    .add(self.version().await.as_bytes())
+                    .add(&[
+                        level,
+                        self.strip_debug as u8,
+                        self.strip_producers as u8,
+                        self.strip_names as u8,
+                    ])
+                    .add(self.passes.join(" ").as_bytes())
+                    .add(self.enable_features.join(" ").as_bytes())
+                    .add(self.disable_features.join(" ").as_bytes())
+                    .add_file(input)?
+                    .finish(),
+            ),
+            None => None,
+        };
+
+        if let (Some(cache), Some(key)) = (&self.cache, &key) {
+            if cache.restore_file(key, output)? {
+                println!("✅ Restored optimized output from cache");
+                return Ok(());
+            }
+        }
+
+        // Decided once for the input, however many core modules it nests
+        if self.wasm_opt_path.is_none() {
+            println!("⚠️  wasm-opt not found, using manual optimization");
+        }
+
+        let wasm = std::fs::read(input)?;
+        if wasmparser::Parser::is_component(&wasm) {
+            self.optimize_component(&wasm, output, level).await?;
+        } else {
+            self.optimize_module(input, output, level).await?;
+        }
+
+        if let (Some(cache), Some(key)) = (&self.cache, &key) {
+            cache.store_file(key, output)?;
+        }
+
+        Ok(())
+    }
+
+    async fn optimize_module(&self, input: &Path, output: &Path, level: u8) -> Result<()> {
         if let Some(wasm_opt) = &self.wasm_opt_path {
             self.optimize_with_wasm_opt(wasm_opt, input, output, level)
                 .await
@@ -24,6 +198,74 @@ impl Optimizer {
         }
     }
 
+    /// Optimizes every core module nested in `component` on its own and
+    /// puts the results back in place, so the component keeps its types,
+    /// imports and exports.
+    async fn optimize_component(&self, component: &[u8], output: &Path, level: u8) -> Result<()> {
+        let modules = nested_modules(component)?;
+        let dir = tempfile::tempdir()?;
+
+        let mut optimized = Vec::with_capacity(modules.len());
+        let mut rows = Vec::with_capacity(modules.len());
+        for module in &modules {
+            println!("📦 Core module #{}", module.index);
+            let input = dir.path().join(format!("module-{}.wasm", module.index));
+            let output = dir
+                .path()
+                .join(format!("module-{}.optimized.wasm", module.index));
+            std::fs::write(
+                &input,
+                &component[module.offset..module.offset + module.size],
+            )?;
+
+            self.optimize_module(&input, &output, level)
+                .await
+                .with_context(|| format!("Failed to optimize core module #{}", module.index))?;
+
+            let bytes = std::fs::read(&output)?;
+            rows.push(vec![
+                format!("#{}", module.index),
+                format_size(module.size as u64),
+                format_size(bytes.len() as u64),
+                format!(
+                    "{:.1}%",
+                    (module.size as f64 - bytes.len() as f64) / module.size.max(1) as f64 * 100.0
+                ),
+            ]);
+            optimized.push(bytes);
+        }
+
+        let mut optimized = optimized.into_iter();
+        let reassembled = rewrite_sections(
+            component,
+            &|name| self.keeps_custom_section(name, level),
+            &mut |_| {
+                optimized.next().ok_or_else(|| {
+                    anyhow!("The component has more core modules than were optimized")
+                })
+            },
+        )?;
+
+        if validate_wasm(component).is_ok() {
+            validate_wasm(&reassembled).map_err(|e| {
+                anyhow!(
+                    "Reassembling the component produced an invalid binary: {}",
+                    e
+                )
+            })?;
+        }
+        std::fs::write(output, reassembled)?;
+
+        println!();
+        println!("{}", "Core Modules:".bright_cyan().bold());
+        print_table(&["Module", "Original", "Optimized", "Reduction"], &rows);
+        println!(
+            "✅ Optimized {} core module(s) of the component",
+            modules.len()
+        );
+        Ok(())
+    }
+
     async fn optimize_with_wasm_opt(
         &self,
         wasm_opt: &str,
@@ -31,35 +273,77 @@ impl Optimizer {
         output: &Path,
         level: u8,
     ) -> Result<()> {
+        let feature_flags = self.feature_flags(&std::fs::read(input)?)?;
         let level_flag = format!("-O{level}");
+        let input = input.to_string_lossy();
+        let output = output.to_string_lossy();
 
-        let mut cmd = Command::new(wasm_opt);
-        cmd.arg(&level_flag).arg(input).arg("-o").arg(output);
-
-        // Add common optimizations
-        cmd.args([
-            "--enable-bulk-memory",
-            "--enable-reference-types",
-            "--enable-simd",
-            "--enable-threads",
-            "--strip-debug",
-            "--strip-producers",
-        ]);
+        let mut args = vec![level_flag.as_str(), &input, "-o", &output];
+        args.extend(feature_flags.iter().map(String::as_str));
 
-        let output_result = cmd.output()?;
-
-        if !output_result.status.success() {
-            let stderr = String::from_utf8_lossy(&output_result.stderr);
-            return Err(anyhow!("wasm-opt failed: {}", stderr));
+        // wasm-opt's --strip-debug removes the name section as well
+        if self.strip_debug || self.strip_names {
+            args.push("--strip-debug");
+        }
+        if self.strip_producers {
+            args.push("--strip-producers");
         }
+        args.extend(self.passes.iter().map(String::as_str));
+
+        run_command(wasm_opt, &args, None)
+            .await
+            .context("wasm-opt failed")?;
 
         println!("✅ Optimized with wasm-opt (level {level})");
         Ok(())
     }
 
-    async fn optimize_manual(&self, input: &Path, output: &Path, level: u8) -> Result<()> {
-        println!("⚠️  wasm-opt not found, using manual optimization");
+    /// wasm-opt arguments limiting it to the proposals `wasm` already uses,
+    /// plus `optimize.enable_features`.
+    fn feature_flags(&self, wasm: &[u8]) -> Result<Vec<String>> {
+        let used = match detect_features(wasm) {
+            Ok(used) => used,
+            Err(e) => {
+                println!("⚠️  Could not detect the features the input uses ({e}); leaving them to wasm-opt");
+                return Ok(self
+                    .disable_features
+                    .iter()
+                    .map(|name| format!("--disable-{name}"))
+                    .collect());
+            }
+        };
+
+        if let Some(name) = used
+            .iter()
+            .find(|name| self.disable_features.iter().any(|d| d == *name))
+        {
+            return Err(anyhow!(
+                "The input uses the {} feature, which `optimize.disable_features` forbids",
+                name
+            ));
+        }
+
+        println!(
+            "🔍 Features in use: {}",
+            if used.is_empty() {
+                "none".to_string()
+            } else {
+                used.join(", ")
+            }
+        );
+
+        let mut flags = vec!["--mvp-features".to_string()];
+        flags.extend(used.iter().map(|name| format!("--enable-{name}")));
+        flags.extend(
+            self.enable_features
+                .iter()
+                .filter(|name| !used.contains(&name.as_str()))
+                .map(|name| format!("--enable-{name}")),
+        );
+        Ok(flags)
+    }
 
+    async fn optimize_manual(&self, input: &Path, output: &Path, level: u8) -> Result<()> {
         let wasm_bytes = std::fs::read(input)?;
         let optimized_bytes = self.manual_optimize(&wasm_bytes, level)?;
 
@@ -70,116 +354,121 @@ impl Optimizer {
     }
 
     fn manual_optimize(&self, wasm_bytes: &[u8], level: u8) -> Result<Vec<u8>> {
-        // Basic manual optimizations
-        let mut optimized = wasm_bytes.to_vec();
-
-        // Remove debug sections
-        optimized = self.remove_debug_sections(&optimized)?;
-
-        // Strip custom sections based on level
-        if level >= 2 {
-            optimized = self.strip_custom_sections(&optimized)?;
+        let mut reachable = None;
+        if level >= 1 && !wasmparser::Parser::is_component(wasm_bytes) {
+            match eliminate_dead_code(wasm_bytes) {
+                Ok(Some((module, removed))) => {
+                    println!("🗑️  Removed unreachable {}", removed);
+                    let dropped: Vec<&str> = removed
+                        .custom_sections
+                        .iter()
+                        .map(String::as_str)
+                        .filter(|name| self.keeps_custom_section(name, level))
+                        .collect();
+                    if !dropped.is_empty() {
+                        println!(
+                            "⚠️  Removed {}: they refer to code that moved",
+                            dropped.join(", ")
+                        );
+                    }
+                    reachable = Some(module);
+                }
+                Ok(None) => {}
+                Err(e) => println!("⚠️  Skipped dead code elimination: {:#}", e),
+            }
         }
 
-        // Further optimizations for higher levels
-        if level >= 3 {
-            optimized = self.optimize_imports_exports(&optimized)?;
+        let optimized =
+            strip_custom_sections(reachable.as_deref().unwrap_or(wasm_bytes), &|name| {
+                self.keeps_custom_section(name, level)
+            })?;
+
+        // Inputs the validator rejects, e.g. for proposals it does not
+        // enable, cannot be checked afterwards either
+        if validate_wasm(wasm_bytes).is_ok() {
+            validate_wasm(&optimized)
+                .map_err(|e| anyhow!("Manual optimization produced an invalid binary: {}", e))?;
         }
 
         Ok(optimized)
     }
 
-    fn remove_debug_sections(&self, wasm_bytes: &[u8]) -> Result<Vec<u8>> {
-        // Parse and rebuild without debug sections
-        let parser = wasmparser::Parser::new(0);
-        let mut new_wasm = Vec::new();
-
-        // Write WASM header
-        new_wasm.extend_from_slice(&[0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00]);
-
-        for payload in parser.parse_all(wasm_bytes) {
-            match payload? {
-                wasmparser::Payload::CustomSection(reader) => {
-                    // Skip debug-related custom sections
-                    let name = reader.name();
-                    if !name.starts_with("debug") && !name.starts_with(".debug") {
-                        // Keep non-debug custom sections
-                        self.write_custom_section(&mut new_wasm, &reader)?;
-                    }
-                }
-                _ => {
-                    // Keep all other sections as-is
-                    // This is a simplified version - full implementation would
-                    // properly reconstruct each section
-                }
-            }
+    /// Whether the custom section `name` survives manual optimization at
+    /// `level`. From level 2 on, only the name section and the sections
+    /// components, dynamic linking and linkers need are kept.
+    fn keeps_custom_section(&self, name: &str, level: u8) -> bool {
+        if self.strip_debug && (name.starts_with(".debug") || name.starts_with("debug")) {
+            return false;
+        }
+        if self.strip_producers && name == "producers" {
+            return false;
+        }
+        if self.strip_names && name == "name" {
+            return false;
         }
 
-        Ok(new_wasm)
+        level < 2
+            || matches!(name, "name" | "producers" | "dylink.0" | "target_features")
+            || name.starts_with("component")
     }
+}
 
-    fn strip_custom_sections(&self, wasm_bytes: &[u8]) -> Result<Vec<u8>> {
-        // Similar to remove_debug_sections but removes more custom sections
-        let parser = wasmparser::Parser::new(0);
-        let mut new_wasm = Vec::new();
-
-        // Write WASM header
-        new_wasm.extend_from_slice(&[0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00]);
+/// Copies `wasm` without the custom sections `keep` rejects, in core modules
+/// and components alike. Every other section, including those of nested
+/// modules and components, is copied byte for byte; only the sizes of nested
+/// modules and components are re-encoded.
+fn strip_custom_sections(wasm: &[u8], keep: &dyn Fn(&str) -> bool) -> Result<Vec<u8>> {
+    rewrite_sections(wasm, keep, &mut |module| {
+        strip_custom_sections(module, keep)
+    })
+}
 
-        for payload in parser.parse_all(wasm_bytes) {
-            match payload? {
-                wasmparser::Payload::CustomSection(reader) => {
-                    // Only keep essential custom sections
-                    let name = reader.name();
-                    if name == "name" || name.starts_with("component") {
-                        self.write_custom_section(&mut new_wasm, &reader)?;
-                    }
-                }
-                _ => {
-                    // Keep all other sections
+/// Copies `wasm` without the custom sections `keep` rejects, replacing each
+/// core module nested in a component, at any depth, with what `module`
+/// returns for it. Modules are visited in the order they appear in the file.
+fn rewrite_sections(
+    wasm: &[u8],
+    keep: &dyn Fn(&str) -> bool,
+    module: &mut dyn FnMut(&[u8]) -> Result<Vec<u8>>,
+) -> Result<Vec<u8>> {
+    const HEADER_LEN: usize = 8;
+    const CUSTOM_SECTION: u8 = 0;
+    const COMPONENT_CORE_MODULE_SECTION: u8 = 1;
+    const COMPONENT_SECTION: u8 = 4;
+
+    if wasm.len() < HEADER_LEN || !wasm.starts_with(b"\0asm") {
+        return Err(anyhow!("Not a WebAssembly binary"));
+    }
+    let component = wasmparser::Parser::is_component(wasm);
+
+    let mut rewritten = wasm[..HEADER_LEN].to_vec();
+    let mut reader = binary_reader(&wasm[HEADER_LEN..]);
+    while !reader.eof() {
+        let start = HEADER_LEN + reader.current_position();
+        let id = reader.read_u8()?;
+        let size = reader.read_var_u32()? as usize;
+        let contents = reader.read_bytes(size)?;
+        let end = HEADER_LEN + reader.current_position();
+
+        let nested = match id {
+            CUSTOM_SECTION => {
+                let name = binary_reader(contents).read_string()?;
+                if keep(name) {
+                    rewritten.extend_from_slice(&wasm[start..end]);
                 }
+                continue;
             }
-        }
-
-        Ok(new_wasm)
-    }
-
-    fn optimize_imports_exports(&self, wasm_bytes: &[u8]) -> Result<Vec<u8>> {
-        // More advanced optimizations would go here
-        // For now, just return the input
-        Ok(wasm_bytes.to_vec())
-    }
-
-    fn write_custom_section(
-        &self,
-        wasm: &mut Vec<u8>,
-        reader: &wasmparser::CustomSectionReader,
-    ) -> Result<()> {
-        // Write custom section header and data
-        wasm.push(0); // Custom section ID
-
-        let name = reader.name();
-        let data = reader.data();
-
-        // Write section size (name length + name + data length)
-        let section_size = name.len() + data.len() + 1; // +1 for name length byte
-        self.write_uleb128(wasm, section_size as u64);
-
-        // Write name
-        self.write_uleb128(wasm, name.len() as u64);
-        wasm.extend_from_slice(name.as_bytes());
-
-        // Write data
-        wasm.extend_from_slice(data);
-
-        Ok(())
+            COMPONENT_CORE_MODULE_SECTION if component => module(contents)?,
+            COMPONENT_SECTION if component => rewrite_sections(contents, keep, module)?,
+            _ => {
+                rewritten.extend_from_slice(&wasm[start..end]);
+                continue;
+            }
+        };
+        rewritten.push(id);
+        write_uleb128(&mut rewritten, nested.len() as u64);
+        rewritten.extend_from_slice(&nested);
     }
 
-    fn write_uleb128(&self, wasm: &mut Vec<u8>, mut value: u64) {
-        while value >= 0x80 {
-            wasm.push((value & 0x7f | 0x80) as u8);
-            value >>= 7;
-        }
-        wasm.push(value as u8);
-    }
+    Ok(rewritten)
 }
@@ -1,3 +1,4 @@
+use crate::config::{BuildSection, ProjectConfig};
 use anyhow::{anyhow, Result};
 use handlebars::Handlebars;
 use serde_json::json;
@@ -338,9 +339,11 @@ export const world = {
 
         match language {
             "rust" => {
-                // Add wasm32-wasip1 target if not present
-                let _ =
-                    run_command("rustup", &["target", "add", "wasm32-wasip1"], Some(path)).await;
+                // Add the target configured by the template if not present
+                let target = ProjectConfig::load_or_default(path)
+                    .map(|config| config.build.target)
+                    .unwrap_or_else(|_| BuildSection::default().target);
+                let _ = run_command("rustup", &["target", "add", &target], Some(path)).await;
             }
             "javascript" | "typescript" => {
                 run_command("npm", &["install"], Some(path)).await?;
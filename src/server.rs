@@ -0,0 +1,356 @@
+use crate::config::CONFIG_FILE_NAME;
+use anyhow::Result;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+
+/// Pages tried, in order, when `/` is requested and no entry page is configured.
+const DEFAULT_PAGES: &[&str] = &["demo.html", "index.html"];
+
+/// Server-sent events endpoint the hot reload client listens on.
+pub const EVENTS_PATH: &str = "/__wasm-wizard/events";
+
+/// Injected into every HTML page when hot reload is enabled.
+const HOT_RELOAD_CLIENT: &str = r#"<script>
+(() => {
+  const events = new EventSource("/__wasm-wizard/events");
+  events.addEventListener("reload", () => location.reload());
+  events.addEventListener("build-error", (event) => {
+    const { message } = JSON.parse(event.data);
+    let overlay = document.getElementById("__wasm-wizard-overlay");
+    if (!overlay) {
+      overlay = document.createElement("div");
+      overlay.id = "__wasm-wizard-overlay";
+      overlay.style.cssText = "position:fixed;inset:0;z-index:2147483647;overflow:auto;" +
+        "padding:2rem;background:rgba(24,24,27,0.95);color:#fca5a5;font:14px/1.5 monospace";
+      document.body.appendChild(overlay);
+    }
+    overlay.innerHTML = "<h2 style='color:#f87171'>Build failed</h2><pre></pre>";
+    overlay.querySelector("pre").textContent = message;
+  });
+})();
+</script>
+"#;
+
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone)]
+enum ReloadEvent {
+    Reload,
+    BuildError(String),
+}
+
+/// Broadcasts rebuild outcomes to the browsers connected to [`EVENTS_PATH`].
+pub struct HotReload {
+    events: broadcast::Sender<ReloadEvent>,
+    /// Shown to pages opened while the last build is still broken
+    last_error: Mutex<Option<String>>,
+}
+
+impl HotReload {
+    pub fn new() -> Self {
+        let (events, _) = broadcast::channel(16);
+        Self {
+            events,
+            last_error: Mutex::new(None),
+        }
+    }
+
+    /// Tells every connected page to reload.
+    pub fn reload(&self) {
+        *self.last_error.lock().unwrap() = None;
+        // Sending only fails when no page is connected
+        let _ = self.events.send(ReloadEvent::Reload);
+    }
+
+    /// Shows the build error as an overlay on every connected page.
+    pub fn build_failed(&self, message: String) {
+        *self.last_error.lock().unwrap() = Some(message.clone());
+        let _ = self.events.send(ReloadEvent::BuildError(message));
+    }
+}
+
+/// Minimal HTTP/1.1 server for `wasm-wizard dev`.
+///
+/// Serves the project directory as static files. `.wasm` files missing from
+/// the project directory are looked up in the build output directories, so
+/// pages can load `/<name>.wasm` straight after a build.
+pub struct DevServer {
+    root: PathBuf,
+    entry: Option<String>,
+    output_dirs: Vec<PathBuf>,
+    hot_reload: Option<Arc<HotReload>>,
+}
+
+impl DevServer {
+    pub fn new(
+        root: &Path,
+        entry: Option<String>,
+        output_dirs: Vec<PathBuf>,
+        hot_reload: Option<Arc<HotReload>>,
+    ) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            entry,
+            output_dirs,
+            hot_reload,
+        }
+    }
+
+    /// Accepts connections until the task is dropped.
+    pub async fn serve(self: Arc<Self>, listener: TcpListener) -> Result<()> {
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let server = self.clone();
+            tokio::spawn(async move {
+                if let Err(e) = server.handle(stream).await {
+                    eprintln!("⚠️  Request failed: {}", e);
+                }
+            });
+        }
+    }
+
+    async fn handle(&self, stream: TcpStream) -> Result<()> {
+        let mut reader = BufReader::new(stream);
+
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line).await? == 0 {
+            return Ok(());
+        }
+
+        // Headers are not needed; read them so the client sees a clean close
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).await? == 0 || line.trim().is_empty() {
+                break;
+            }
+        }
+
+        let mut stream = reader.into_inner();
+        let mut parts = request_line.split_whitespace();
+        let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+            return respond(
+                &mut stream,
+                "400 Bad Request",
+                "text/plain",
+                b"Bad Request",
+                false,
+            )
+            .await;
+        };
+
+        let head_only = method == "HEAD";
+        if method != "GET" && !head_only {
+            return respond(
+                &mut stream,
+                "405 Method Not Allowed",
+                "text/plain",
+                b"Method Not Allowed",
+                false,
+            )
+            .await;
+        }
+
+        let path = target.split(['?', '#']).next().unwrap_or("/");
+        if let (EVENTS_PATH, Some(hot_reload)) = (path, &self.hot_reload) {
+            return stream_events(stream, hot_reload).await;
+        }
+
+        match self.resolve(&percent_decode(path)) {
+            Some(file) => {
+                let mut body = tokio::fs::read(&file).await?;
+                let content_type = content_type(&file);
+                if self.hot_reload.is_some() && content_type.starts_with("text/html") {
+                    body = inject_client(&body);
+                }
+                respond(&mut stream, "200 OK", content_type, &body, head_only).await
+            }
+            None => {
+                respond(
+                    &mut stream,
+                    "404 Not Found",
+                    "text/plain",
+                    b"Not Found",
+                    head_only,
+                )
+                .await
+            }
+        }
+    }
+
+    /// Maps a request path to a file, refusing anything outside the project,
+    /// dotfiles such as `.git/` and `.env`, and the project configuration.
+    fn resolve(&self, request_path: &str) -> Option<PathBuf> {
+        let relative = Path::new(request_path.trim_start_matches('/'));
+        let hidden = |c: Component| match c {
+            Component::Normal(name) => name.to_string_lossy().starts_with('.'),
+            _ => true,
+        };
+        if relative.components().any(hidden) || relative == Path::new(CONFIG_FILE_NAME) {
+            return None;
+        }
+
+        if relative.as_os_str().is_empty() {
+            return self
+                .entry
+                .iter()
+                .map(String::as_str)
+                .chain(DEFAULT_PAGES.iter().copied())
+                .map(|page| self.root.join(page))
+                .find(|path| path.is_file());
+        }
+
+        let path = self.root.join(relative);
+        if path.is_file() {
+            return Some(path);
+        }
+        if path.is_dir() {
+            let index = path.join("index.html");
+            return index.is_file().then_some(index);
+        }
+
+        if crate::utils::is_wasm_file(&path) {
+            let file_name = relative.file_name()?;
+            return self
+                .output_dirs
+                .iter()
+                .map(|dir| dir.join(file_name))
+                .find(|path| path.is_file());
+        }
+
+        None
+    }
+}
+
+/// Keeps the connection open and forwards reload events as server-sent events.
+async fn stream_events(mut stream: TcpStream, hot_reload: &HotReload) -> Result<()> {
+    let mut events = hot_reload.events.subscribe();
+
+    stream
+        .write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-store\r\nConnection: keep-alive\r\n\r\nretry: 1000\n\n",
+        )
+        .await?;
+
+    let last_error = hot_reload.last_error.lock().unwrap().clone();
+    if let Some(message) = last_error {
+        stream
+            .write_all(event_message(&ReloadEvent::BuildError(message)).as_bytes())
+            .await?;
+    }
+
+    let mut keep_alive = tokio::time::interval(KEEP_ALIVE_INTERVAL);
+    loop {
+        let message = tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => event_message(&event),
+                // Missed events only matter for their effect, which a reload covers
+                Err(broadcast::error::RecvError::Lagged(_)) => event_message(&ReloadEvent::Reload),
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+            _ = keep_alive.tick() => ": keep-alive\n\n".to_string(),
+        };
+
+        // A failed write means the page went away
+        if stream.write_all(message.as_bytes()).await.is_err() {
+            return Ok(());
+        }
+    }
+}
+
+fn event_message(event: &ReloadEvent) -> String {
+    match event {
+        ReloadEvent::Reload => "event: reload\ndata: {}\n\n".to_string(),
+        ReloadEvent::BuildError(message) => format!(
+            "event: build-error\ndata: {}\n\n",
+            serde_json::json!({ "message": message })
+        ),
+    }
+}
+
+/// Inserts the hot reload client before `</body>`, or at the end of the page.
+fn inject_client(html: &[u8]) -> Vec<u8> {
+    let html = String::from_utf8_lossy(html);
+    let position = html
+        .to_ascii_lowercase()
+        .rfind("</body>")
+        .unwrap_or(html.len());
+
+    let mut injected = String::with_capacity(html.len() + HOT_RELOAD_CLIENT.len());
+    injected.push_str(&html[..position]);
+    injected.push_str(HOT_RELOAD_CLIENT);
+    injected.push_str(&html[position..]);
+    injected.into_bytes()
+}
+
+async fn respond(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+    head_only: bool,
+) -> Result<()> {
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+
+    stream.write_all(head.as_bytes()).await?;
+    if !head_only {
+        stream.write_all(body).await?;
+    }
+    stream.shutdown().await?;
+    Ok(())
+}
+
+fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "wasm" => "application/wasm",
+        "html" | "htm" => "text/html; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "json" | "map" => "application/json",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "ico" => "image/x-icon",
+        "txt" | "wit" | "md" | "toml" => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
@@ -1,23 +1,288 @@
-use anyhow::{anyhow, Result};
+use crate::analyzer::{decode_world, nested_modules, CoreModuleInfo, ModuleProfile, WorldInfo};
+use crate::config::ProjectConfig;
+use anyhow::{anyhow, Context, Result};
+use indicatif::ProgressBar;
+use serde::Serialize;
+use std::future::Future;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::sync::watch;
+
+/// How [`run_command_with`] runs a command.
+#[derive(Clone, Default)]
+pub struct RunOptions {
+    /// Echo the output as it arrives instead of only capturing it
+    pub verbose: bool,
+    /// Kill the command once it has run this long
+    pub timeout: Option<Duration>,
+    /// Spinner hidden while output is echoed
+    pub progress: Option<ProgressBar>,
+    /// Never echo stdout, e.g. when it carries machine-readable messages
+    pub quiet_stdout: bool,
+    /// Extra environment variables
+    pub env: Vec<(&'static str, String)>,
+}
+
+/// What a command printed.
+pub struct CommandOutput {
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// A command that did not exit successfully, with what it printed.
+#[derive(Debug)]
+pub struct CommandError {
+    pub command: String,
+    pub failure: CommandFailure,
+    pub stdout: String,
+    pub stderr: String,
+    /// The output was already echoed, so the message leaves it out
+    echoed: bool,
+}
+
+#[derive(Debug)]
+pub enum CommandFailure {
+    /// Exited with the code, or was killed by a signal when there is none
+    Exited(Option<i32>),
+    TimedOut(Duration),
+    /// Cancelled with Ctrl-C
+    Interrupted,
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.failure {
+            CommandFailure::Exited(Some(code)) => write!(
+                f,
+                "Command '{}' failed with exit code {}",
+                self.command, code
+            )?,
+            CommandFailure::Exited(None) => {
+                write!(f, "Command '{}' was killed by a signal", self.command)?
+            }
+            CommandFailure::TimedOut(timeout) => write!(
+                f,
+                "Command '{}' timed out after {}s",
+                self.command,
+                timeout.as_secs_f64()
+            )?,
+            CommandFailure::Interrupted => write!(f, "Command '{}' was interrupted", self.command)?,
+        }
+
+        // Tools print their errors to stderr, except for a few that use stdout
+        let logs = if self.stderr.trim().is_empty() {
+            self.stdout.trim()
+        } else {
+            self.stderr.trim()
+        };
+        if !self.echoed && !logs.is_empty() {
+            write!(f, ":\n{}", logs)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+/// Whether Ctrl-C was pressed; its receivers are whoever waits in [`interrupted`].
+fn interrupt() -> &'static watch::Sender<bool> {
+    static INTERRUPT: OnceLock<watch::Sender<bool>> = OnceLock::new();
+    INTERRUPT.get_or_init(|| watch::channel(false).0)
+}
+
+/// Handles Ctrl-C for the whole process, once, from `main`.
+///
+/// Running commands are killed and watch loops stop through [`interrupted`];
+/// when nothing waits on it, the process exits right away.
+pub fn listen_for_ctrl_c() {
+    tokio::spawn(async {
+        while tokio::signal::ctrl_c().await.is_ok() {
+            let interrupt = interrupt();
+            if interrupt.receiver_count() == 0 {
+                std::process::exit(130);
+            }
+            interrupt.send_replace(true);
+        }
+    });
+}
+
+/// Completes once Ctrl-C is pressed. Waiting starts when this is called, not
+/// when the future is first polled.
+pub fn interrupted() -> impl Future<Output = ()> {
+    let mut receiver = interrupt().subscribe();
+    async move {
+        let _ = receiver.wait_for(|&pressed| pressed).await;
+    }
+}
 
 pub async fn run_command(cmd: &str, args: &[&str], cwd: Option<&Path>) -> Result<()> {
-    let mut command = Command::new(cmd);
-    command.args(args);
+    run_command_with(cmd, args, cwd, &RunOptions::default()).await?;
+    Ok(())
+}
+
+/// Like [`run_command`], but returns what the command printed to stdout.
+pub async fn run_command_output(cmd: &str, args: &[&str], cwd: Option<&Path>) -> Result<String> {
+    Ok(run_command_with(cmd, args, cwd, &RunOptions::default())
+        .await?
+        .stdout)
+}
+
+/// Runs `cmd` without blocking the runtime and captures its output.
+///
+/// Failures, timeouts and Ctrl-C end in a [`CommandError`] carrying the exit
+/// code and the captured output; the command is killed in the latter two.
+pub async fn run_command_with(
+    cmd: &str,
+    args: &[&str],
+    cwd: Option<&Path>,
+    options: &RunOptions,
+) -> Result<CommandOutput> {
+    let command_line = std::iter::once(cmd)
+        .chain(args.iter().copied())
+        .collect::<Vec<_>>()
+        .join(" ");
 
+    let mut command = tokio::process::Command::new(cmd);
+    command
+        .args(args)
+        .envs(options.env.iter().map(|(name, value)| (name, value)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
     if let Some(dir) = cwd {
         command.current_dir(dir);
     }
 
-    let output = command.output()?;
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("Failed to run '{}'", command_line))?;
+
+    let echo = |to_stderr: bool, quiet: bool| {
+        (options.verbose && !quiet).then(|| Echo {
+            to_stderr,
+            progress: options.progress.clone(),
+        })
+    };
+    let (stdout, stderr) = (SharedOutput::default(), SharedOutput::default());
+    let stdout_task = tokio::spawn(collect_output(
+        child.stdout.take(),
+        stdout.clone(),
+        echo(false, options.quiet_stdout),
+    ));
+    let stderr_task = tokio::spawn(collect_output(
+        child.stderr.take(),
+        stderr.clone(),
+        echo(true, false),
+    ));
+
+    let deadline = async {
+        match options.timeout {
+            Some(timeout) => tokio::time::sleep(timeout).await,
+            None => std::future::pending().await,
+        }
+    };
+    let interrupted = interrupted();
+    let failure = tokio::select! {
+        status = child.wait() => {
+            let status = status?;
+            (!status.success()).then(|| CommandFailure::Exited(status.code()))
+        }
+        _ = deadline => Some(CommandFailure::TimedOut(options.timeout.unwrap_or_default())),
+        _ = interrupted => Some(CommandFailure::Interrupted),
+    };
+
+    let killed = matches!(
+        failure,
+        Some(CommandFailure::TimedOut(_) | CommandFailure::Interrupted)
+    );
+    if killed {
+        let _ = child.kill().await;
+    }
+
+    // Processes the command started may keep the pipes open after it was
+    // killed; keep what they printed so far instead of waiting for them
+    let readers = async {
+        let _ = tokio::join!(stdout_task, stderr_task);
+    };
+    if killed {
+        let _ = tokio::time::timeout(Duration::from_secs(1), readers).await;
+    } else {
+        readers.await;
+    }
+    let output = CommandOutput {
+        stdout: stdout.take(),
+        stderr: stderr.take(),
+    };
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(anyhow!("Command '{}' failed: {}", cmd, stderr));
+    match failure {
+        None => Ok(output),
+        Some(failure) => Err(CommandError {
+            command: command_line,
+            failure,
+            stdout: output.stdout,
+            stderr: output.stderr,
+            echoed: options.verbose,
+        }
+        .into()),
     }
+}
 
-    Ok(())
+/// Where [`collect_output`] echoes the lines it reads.
+struct Echo {
+    to_stderr: bool,
+    progress: Option<ProgressBar>,
+}
+
+/// Bytes read from a command's pipe so far.
+#[derive(Clone, Default)]
+struct SharedOutput(Arc<Mutex<Vec<u8>>>);
+
+impl SharedOutput {
+    fn take(&self) -> String {
+        let bytes = std::mem::take(&mut *self.0.lock().unwrap());
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+}
+
+async fn collect_output(
+    reader: Option<impl AsyncRead + Unpin>,
+    output: SharedOutput,
+    echo: Option<Echo>,
+) {
+    let Some(reader) = reader else {
+        return;
+    };
+
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+
+        if let Some(echo) = &echo {
+            let text = String::from_utf8_lossy(&line);
+            let text = text.trim_end_matches(['\n', '\r']);
+            let print = || {
+                if echo.to_stderr {
+                    eprintln!("{}", text);
+                } else {
+                    println!("{}", text);
+                }
+            };
+            match &echo.progress {
+                Some(pb) => pb.suspend(print),
+                None => print(),
+            }
+        }
+        output.0.lock().unwrap().extend_from_slice(&line);
+    }
 }
 
 pub fn find_project_root() -> Result<PathBuf> {
@@ -42,12 +307,67 @@ pub fn find_project_root() -> Result<PathBuf> {
     Err(anyhow!("Could not find project root"))
 }
 
-#[allow(dead_code)]
+/// Directories where the project's toolchains write their `.wasm` outputs.
+pub fn build_output_dirs(project_root: &Path, config: &ProjectConfig) -> Vec<PathBuf> {
+    let target_dir = project_root.join("target").join(&config.build.target);
+
+    let mut dirs = vec![target_dir.join("release"), target_dir.join("debug")];
+    // Custom cargo profiles build into a directory of their own name
+    for profile in config.profile.values() {
+        if let Some(cargo_profile) = &profile.cargo_profile {
+            let dir = target_dir.join(cargo_profile);
+            if !matches!(cargo_profile.as_str(), "dev" | "test" | "bench") && !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+    }
+    // wasm-pack and JavaScript toolchains
+    dirs.extend([project_root.join("pkg"), project_root.join("dist")]);
+    dirs
+}
+
 pub fn is_wasm_file(path: &Path) -> bool {
     path.extension().is_some_and(|ext| ext == "wasm")
 }
 
-#[allow(dead_code)]
+/// Whether `path` is an `.optimized.wasm` or `.component.wasm` file, or one
+/// named after a profile's `output_name`, that `build` derived from a
+/// toolchain output.
+pub fn is_derived_artifact(path: &Path, config: &ProjectConfig) -> bool {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    file_name.ends_with(".optimized.wasm")
+        || file_name.ends_with(".component.wasm")
+        || config.is_profile_output(&file_name)
+}
+
+/// Prints `rows` as left-aligned columns under bold `headers`.
+pub fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+    use colored::Colorize;
+
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let format_row = |cells: Vec<&str>| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    println!("  {}", format_row(headers.to_vec()).bold());
+    for row in rows {
+        println!("  {}", format_row(row.iter().map(String::as_str).collect()));
+    }
+}
+
 pub fn format_size(bytes: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB"];
     let mut size = bytes as f64;
@@ -65,72 +385,295 @@ pub fn format_size(bytes: u64) -> String {
     }
 }
 
-#[allow(dead_code)]
+/// Reader over raw binary contents, e.g. a section or a custom section name.
+pub fn binary_reader(data: &[u8]) -> wasmparser::BinaryReader<'_> {
+    wasmparser::BinaryReader::new(data, 0, validation_features())
+}
+
+/// Features accepted when validating: every standardized proposal enabled by
+/// default in wasmparser, plus the component model.
+pub fn validation_features() -> wasmparser::WasmFeatures {
+    wasmparser::WasmFeatures::default() | wasmparser::WasmFeatures::COMPONENT_MODEL
+}
+
+/// Fully validates a core module or component, including nested modules.
+pub fn validate_wasm(wasm_bytes: &[u8]) -> std::result::Result<(), wasmparser::BinaryReaderError> {
+    wasmparser::Validator::new_with_features(validation_features())
+        .validate_all(wasm_bytes)
+        .map(|_| ())
+}
+
+/// Parses a size such as `51200`, `50kb` or `1.5mb`; units are powers of 1024,
+/// as in [`format_size`].
+pub fn parse_size(size: &str) -> std::result::Result<u64, String> {
+    let lower = size.trim().to_ascii_lowercase();
+    let (number, unit) = match lower.find(|c: char| c.is_ascii_alphabetic()) {
+        Some(index) => lower.split_at(index),
+        None => (lower.as_str(), "b"),
+    };
+    let multiplier = match unit.trim() {
+        "b" => 1,
+        "k" | "kb" => 1024,
+        "m" | "mb" => 1024 * 1024,
+        _ => return Err(format!("unknown size unit in '{}'; use b, kb or mb", size)),
+    };
+
+    number
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|number| number.is_finite() && *number >= 0.0)
+        .map(|number| (number * multiplier as f64) as u64)
+        .ok_or_else(|| format!("invalid size '{}'", size))
+}
+
+/// Appends `value` in the LEB128 encoding WebAssembly uses for sizes and
+/// indices.
+pub fn write_uleb128(wasm: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        wasm.push((value & 0x7f | 0x80) as u8);
+        value >>= 7;
+    }
+    wasm.push(value as u8);
+}
+
+/// Version of the `WasmInfo` JSON document printed by `analyze --format json`.
+///
+/// Bump it whenever a field is renamed, removed or changes meaning; adding
+/// fields is backwards compatible and does not require a bump.
+pub const WASM_INFO_SCHEMA_VERSION: u32 = 1;
+
 pub fn get_wasm_info(path: &Path) -> Result<WasmInfo> {
     let wasm_bytes = std::fs::read(path)?;
-    let parser = wasmparser::Parser::new(0);
+    let is_component = wasmparser::Parser::is_component(&wasm_bytes);
 
     let mut info = WasmInfo {
+        schema_version: WASM_INFO_SCHEMA_VERSION,
+        file: path.display().to_string(),
         size: wasm_bytes.len(),
-        imports: 0,
-        exports: 0,
+        kind: if is_component { "component" } else { "module" }.to_string(),
         functions: 0,
         sections: Vec::new(),
+        custom_sections: Vec::new(),
+        imports: Vec::new(),
+        exports: Vec::new(),
+        modules: nested_modules(&wasm_bytes)?,
+        world: None,
+        profiles: None,
     };
 
-    for payload in parser.parse_all(&wasm_bytes) {
-        match payload {
-            Ok(wasmparser::Payload::ImportSection(reader)) => {
-                info.imports = reader.count() as usize;
-                info.sections.push("Import".to_string());
-            }
-            Ok(wasmparser::Payload::ExportSection(reader)) => {
-                info.exports = reader.count() as usize;
-                info.sections.push("Export".to_string());
-            }
-            Ok(wasmparser::Payload::FunctionSection(reader)) => {
-                info.functions = reader.count() as usize;
-                info.sections.push("Function".to_string());
-            }
-            Ok(wasmparser::Payload::TypeSection(_)) => {
-                info.sections.push("Type".to_string());
-            }
-            Ok(wasmparser::Payload::MemorySection(_)) => {
-                info.sections.push("Memory".to_string());
-            }
-            Ok(wasmparser::Payload::GlobalSection(_)) => {
-                info.sections.push("Global".to_string());
-            }
-            Ok(wasmparser::Payload::TableSection(_)) => {
-                info.sections.push("Table".to_string());
+    // Sections of nested modules and components are reported through `modules`
+    let mut depth = 0;
+    for payload in wasmparser::Parser::new(0).parse_all(&wasm_bytes) {
+        let payload = payload?;
+
+        if let wasmparser::Payload::FunctionSection(reader) = &payload {
+            info.functions += reader.count() as usize;
+        }
+
+        if depth == 0 {
+            if let Some((_, range)) = payload.as_section() {
+                match &payload {
+                    wasmparser::Payload::CustomSection(reader) => {
+                        info.custom_sections.push(SectionInfo {
+                            name: reader.name().to_string(),
+                            offset: range.start,
+                            size: range.len(),
+                        });
+                    }
+                    _ => {
+                        if let Some(name) = section_name(&payload) {
+                            info.sections.push(SectionInfo {
+                                name: name.to_string(),
+                                offset: range.start,
+                                size: range.len(),
+                            });
+                        }
+                    }
+                }
             }
-            Ok(wasmparser::Payload::ElementSection(_)) => {
-                info.sections.push("Element".to_string());
+
+            collect_imports_exports(&payload, &mut info)?;
+        }
+
+        match payload {
+            wasmparser::Payload::ModuleSection { .. }
+            | wasmparser::Payload::ComponentSection { .. } => depth += 1,
+            wasmparser::Payload::End(_) if depth > 0 => depth -= 1,
+            _ => {}
+        }
+    }
+
+    // Core modules only carry a world when built with wit-bindgen, so a
+    // malformed `component-type` section there is not worth failing over
+    info.world = if is_component {
+        decode_world(&wasm_bytes)?
+    } else {
+        decode_world(&wasm_bytes).ok().flatten()
+    };
+
+    Ok(info)
+}
+
+fn collect_imports_exports(payload: &wasmparser::Payload, info: &mut WasmInfo) -> Result<()> {
+    match payload {
+        wasmparser::Payload::ImportSection(reader) => {
+            for import in reader.clone() {
+                let import = import?;
+                let kind = match import.ty {
+                    wasmparser::TypeRef::Func(_) => "func",
+                    wasmparser::TypeRef::Table(_) => "table",
+                    wasmparser::TypeRef::Memory(_) => "memory",
+                    wasmparser::TypeRef::Global(_) => "global",
+                    wasmparser::TypeRef::Tag(_) => "tag",
+                };
+                info.imports.push(ImportEntry {
+                    module: Some(import.module.to_string()),
+                    name: import.name.to_string(),
+                    kind: kind.to_string(),
+                });
             }
-            Ok(wasmparser::Payload::DataSection(_)) => {
-                info.sections.push("Data".to_string());
+        }
+        wasmparser::Payload::ExportSection(reader) => {
+            for export in reader.clone() {
+                let export = export?;
+                let kind = match export.kind {
+                    wasmparser::ExternalKind::Func => "func",
+                    wasmparser::ExternalKind::Table => "table",
+                    wasmparser::ExternalKind::Memory => "memory",
+                    wasmparser::ExternalKind::Global => "global",
+                    wasmparser::ExternalKind::Tag => "tag",
+                };
+                info.exports.push(ExportEntry {
+                    name: export.name.to_string(),
+                    kind: kind.to_string(),
+                });
             }
-            Ok(wasmparser::Payload::CodeSectionStart { .. }) => {
-                info.sections.push("Code".to_string());
+        }
+        wasmparser::Payload::ComponentImportSection(reader) => {
+            for import in reader.clone() {
+                let import = import?;
+                let kind = match import.ty {
+                    wasmparser::ComponentTypeRef::Module(_) => "module",
+                    wasmparser::ComponentTypeRef::Func(_) => "func",
+                    wasmparser::ComponentTypeRef::Value(_) => "value",
+                    wasmparser::ComponentTypeRef::Type(_) => "type",
+                    wasmparser::ComponentTypeRef::Instance(_) => "instance",
+                    wasmparser::ComponentTypeRef::Component(_) => "component",
+                };
+                info.imports.push(ImportEntry {
+                    module: None,
+                    name: import.name.0.to_string(),
+                    kind: kind.to_string(),
+                });
             }
-            Ok(wasmparser::Payload::CustomSection(reader)) => {
-                info.sections.push(format!("Custom({})", reader.name()));
+        }
+        wasmparser::Payload::ComponentExportSection(reader) => {
+            for export in reader.clone() {
+                let export = export?;
+                let kind = match export.kind {
+                    wasmparser::ComponentExternalKind::Module => "module",
+                    wasmparser::ComponentExternalKind::Func => "func",
+                    wasmparser::ComponentExternalKind::Value => "value",
+                    wasmparser::ComponentExternalKind::Type => "type",
+                    wasmparser::ComponentExternalKind::Instance => "instance",
+                    wasmparser::ComponentExternalKind::Component => "component",
+                };
+                info.exports.push(ExportEntry {
+                    name: export.name.0.to_string(),
+                    kind: kind.to_string(),
+                });
             }
-            _ => {}
         }
+        _ => {}
     }
 
-    Ok(info)
+    Ok(())
 }
 
-#[allow(dead_code)]
-#[derive(Debug)]
+/// Display name of a known, non-custom section.
+pub fn section_name(payload: &wasmparser::Payload) -> Option<&'static str> {
+    use wasmparser::Payload::*;
+
+    let name = match payload {
+        TypeSection(_) => "Type",
+        ImportSection(_) => "Import",
+        FunctionSection(_) => "Function",
+        TableSection(_) => "Table",
+        MemorySection(_) => "Memory",
+        TagSection(_) => "Tag",
+        GlobalSection(_) => "Global",
+        ExportSection(_) => "Export",
+        StartSection { .. } => "Start",
+        ElementSection(_) => "Element",
+        DataCountSection { .. } => "DataCount",
+        DataSection(_) => "Data",
+        CodeSectionStart { .. } => "Code",
+        ModuleSection { .. } => "Module",
+        InstanceSection(_) => "CoreInstance",
+        CoreTypeSection(_) => "CoreType",
+        ComponentSection { .. } => "Component",
+        ComponentInstanceSection(_) => "ComponentInstance",
+        ComponentAliasSection(_) => "ComponentAlias",
+        ComponentTypeSection(_) => "ComponentType",
+        ComponentCanonicalSection(_) => "ComponentCanonical",
+        ComponentStartSection { .. } => "ComponentStart",
+        ComponentImportSection(_) => "ComponentImport",
+        ComponentExportSection(_) => "ComponentExport",
+        _ => return None,
+    };
+
+    Some(name)
+}
+
+/// Machine-readable description of a WebAssembly module or component.
+///
+/// Serialized as-is by `analyze --format json`; the schema is documented in
+/// the README and versioned by `schema_version`.
+#[derive(Debug, Serialize)]
 pub struct WasmInfo {
+    pub schema_version: u32,
+    pub file: String,
     pub size: usize,
-    pub imports: usize,
-    pub exports: usize,
+    /// "module" or "component"
+    pub kind: String,
+    /// Defined core functions, including those of nested modules
     pub functions: usize,
-    pub sections: Vec<String>,
+    /// Top-level known sections, in binary order
+    pub sections: Vec<SectionInfo>,
+    /// Top-level custom sections, in binary order
+    pub custom_sections: Vec<SectionInfo>,
+    pub imports: Vec<ImportEntry>,
+    pub exports: Vec<ExportEntry>,
+    /// Core modules nested in a component; empty for core modules
+    pub modules: Vec<CoreModuleInfo>,
+    pub world: Option<WorldInfo>,
+    /// Size breakdown of each core module, only present with `--detailed`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profiles: Option<Vec<ModuleProfile>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SectionInfo {
+    pub name: String,
+    /// Offset of the section payload in the file
+    pub offset: usize,
+    /// Size of the section payload in bytes
+    pub size: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportEntry {
+    /// Module name for core imports; `None` for component imports
+    pub module: Option<String>,
+    pub name: String,
+    pub kind: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportEntry {
+    pub name: String,
+    pub kind: String,
 }
 
 #[allow(dead_code)]
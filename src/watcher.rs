@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use walkdir::WalkDir;
+
+/// Directories holding build outputs or dependencies; changes there never
+/// trigger a rebuild, otherwise every build would schedule the next one.
+pub const IGNORED_DIRS: &[&str] = &["target", "node_modules", ".git", "pkg", "dist"];
+
+const POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+/// Polls the files of a project matching glob-style patterns for changes.
+///
+/// Patterns are relative to the project root and support `*`, `?` and `**`;
+/// a pattern naming a directory matches everything below it.
+pub struct Watcher {
+    root: PathBuf,
+    patterns: Vec<String>,
+    snapshot: HashMap<PathBuf, (SystemTime, u64)>,
+}
+
+impl Watcher {
+    pub fn new(root: &Path, patterns: &[String]) -> Self {
+        let mut watcher = Self {
+            root: root.to_path_buf(),
+            patterns: patterns.to_vec(),
+            snapshot: HashMap::new(),
+        };
+        watcher.snapshot = watcher.scan();
+        watcher
+    }
+
+    /// Waits until watched files are added, modified or removed, then until
+    /// no further change happened for `debounce`. Returns the changed paths.
+    pub async fn wait_for_changes(&mut self, debounce: Duration) -> Vec<PathBuf> {
+        let mut changed = loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let changed = self.rescan();
+            if !changed.is_empty() {
+                break changed;
+            }
+        };
+
+        // Editors and formatters often write several files in a row
+        loop {
+            tokio::time::sleep(debounce).await;
+            let more = self.rescan();
+            if more.is_empty() {
+                break;
+            }
+            changed.extend(more);
+        }
+
+        changed.sort();
+        changed.dedup();
+        changed
+    }
+
+    fn rescan(&mut self) -> Vec<PathBuf> {
+        let current = self.scan();
+
+        let mut changed: Vec<PathBuf> = current
+            .iter()
+            .filter(|(path, state)| self.snapshot.get(*path) != Some(state))
+            .map(|(path, _)| path.clone())
+            .collect();
+        changed.extend(
+            self.snapshot
+                .keys()
+                .filter(|path| !current.contains_key(*path))
+                .cloned(),
+        );
+
+        self.snapshot = current;
+        changed
+    }
+
+    fn scan(&self) -> HashMap<PathBuf, (SystemTime, u64)> {
+        let mut files = HashMap::new();
+
+        let entries = WalkDir::new(&self.root)
+            .into_iter()
+            .filter_entry(|entry| {
+                entry.depth() == 0
+                    || !entry.file_type().is_dir()
+                    || !IGNORED_DIRS.contains(&entry.file_name().to_string_lossy().as_ref())
+            })
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file());
+
+        for entry in entries {
+            let Ok(relative) = entry.path().strip_prefix(&self.root) else {
+                continue;
+            };
+            let relative = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            if !self.patterns.iter().any(|p| matches_pattern(p, &relative)) {
+                continue;
+            }
+
+            if let Ok(metadata) = entry.metadata() {
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                files.insert(entry.into_path(), (modified, metadata.len()));
+            }
+        }
+
+        files
+    }
+}
+
+fn matches_pattern(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+    glob_match(pattern, path) || glob_match(&format!("{}/**", pattern), path)
+}
+
+fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+    let path: Vec<&str> = path.split('/').collect();
+    match_segments(&pattern, &path)
+}
+
+fn match_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|i| match_segments(rest, &path[i..])),
+        Some((segment, rest)) => {
+            !path.is_empty()
+                && match_segment(segment.as_bytes(), path[0].as_bytes())
+                && match_segments(rest, &path[1..])
+        }
+    }
+}
+
+fn match_segment(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|i| match_segment(rest, &name[i..])),
+        Some((b'?', rest)) => !name.is_empty() && match_segment(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && match_segment(rest, &name[1..]),
+    }
+}
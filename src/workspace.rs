@@ -0,0 +1,227 @@
+use crate::config::ProjectConfig;
+use crate::utils::{build_output_dirs, is_derived_artifact, is_wasm_file};
+use anyhow::{anyhow, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use wit_parser::{Resolve, WorldItem};
+
+/// A project listed in `[workspace] members`.
+#[derive(Debug, Clone)]
+pub struct Member {
+    pub name: String,
+    pub root: PathBuf,
+    pub config: ProjectConfig,
+    /// Interfaces the member's world imports, as `namespace:package/name`
+    imports: BTreeSet<String>,
+    /// Interfaces the member's world exports, as `namespace:package/name`
+    exports: BTreeSet<String>,
+}
+
+/// The members of a workspace, ordered by the interfaces they share.
+///
+/// A member importing an interface that another member exports depends on
+/// it. Members are grouped into levels: every member only depends on members
+/// of earlier levels, so the members of one level can be built in parallel.
+#[derive(Debug)]
+pub struct Workspace {
+    pub members: Vec<Member>,
+    /// Indices into `members`, one group per level
+    pub levels: Vec<Vec<usize>>,
+    /// Indices of the members each member depends on
+    pub dependencies: Vec<Vec<usize>>,
+}
+
+impl Workspace {
+    /// Loads the members listed in `config`; `None` when it lists none.
+    pub fn load(root: &Path, config: &ProjectConfig) -> Result<Option<Self>> {
+        if config.workspace.is_empty() {
+            return Ok(None);
+        }
+
+        let members = config
+            .workspace
+            .members
+            .iter()
+            .map(|path| Member::load(root, path))
+            .collect::<Result<Vec<_>>>()?;
+
+        let dependencies: Vec<Vec<usize>> = members
+            .iter()
+            .enumerate()
+            .map(|(i, member)| {
+                (0..members.len())
+                    .filter(|&j| j != i && !member.imports.is_disjoint(&members[j].exports))
+                    .collect()
+            })
+            .collect();
+
+        let levels = build_levels(&members, &dependencies)?;
+
+        Ok(Some(Self {
+            members,
+            levels,
+            dependencies,
+        }))
+    }
+
+    /// Members in dependency order.
+    pub fn ordered(&self) -> impl Iterator<Item = &Member> {
+        self.levels
+            .iter()
+            .flatten()
+            .map(|&index| &self.members[index])
+    }
+}
+
+impl Member {
+    fn load(workspace_root: &Path, path: &str) -> Result<Self> {
+        let root = workspace_root.join(path);
+        if !root.is_dir() {
+            return Err(anyhow!(
+                "Workspace member \"{}\" not found at {}",
+                path,
+                root.display()
+            ));
+        }
+
+        let config = ProjectConfig::load_or_default(&root)?;
+        let name = if config.project.name.is_empty() {
+            path.trim_end_matches('/').to_string()
+        } else {
+            config.project.name.clone()
+        };
+
+        let (imports, exports) = world_interfaces(&root.join("wit"), config.build.world.as_deref())
+            .map_err(|e| anyhow!("Workspace member \"{}\": {:#}", name, e))?;
+
+        Ok(Self {
+            name,
+            root,
+            config,
+            imports,
+            exports,
+        })
+    }
+
+    /// Modules the member's toolchain left in its build output directories.
+    pub fn build_outputs(&self) -> Vec<PathBuf> {
+        self.wasm_files()
+            .into_iter()
+            .filter(|path| !is_derived_artifact(path, &self.config))
+            .collect()
+    }
+
+    /// Final artifacts of the member's builds: for each build output, the
+    /// component or optimized file derived from it when present. Artifacts
+    /// restored from the cache may exist without their build output.
+    pub fn artifacts(&self) -> Vec<PathBuf> {
+        let mut artifacts: BTreeMap<PathBuf, PathBuf> = BTreeMap::new();
+
+        for path in self.wasm_files() {
+            let mut base = path.with_extension("");
+            if matches!(
+                base.extension().and_then(|ext| ext.to_str()),
+                Some("component" | "optimized")
+            ) {
+                base = base.with_extension("");
+            }
+
+            let best = artifacts.entry(base).or_insert_with(|| path.clone());
+            if artifact_rank(&path) < artifact_rank(best) {
+                *best = path;
+            }
+        }
+
+        artifacts.into_values().collect()
+    }
+
+    fn wasm_files(&self) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+
+        for dir in build_output_dirs(&self.root, &self.config) {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.filter_map(|entry| entry.ok()) {
+                let path = entry.path();
+                if path.is_file() && is_wasm_file(&path) {
+                    files.push(path);
+                }
+            }
+        }
+
+        files.sort();
+        files
+    }
+}
+
+/// Components beat optimized modules, which beat plain build outputs.
+fn artifact_rank(path: &Path) -> u8 {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    if file_name.ends_with(".component.wasm") {
+        0
+    } else if file_name.ends_with(".optimized.wasm") {
+        1
+    } else {
+        2
+    }
+}
+
+/// Interfaces imported and exported by the world in `wit_dir`, if any.
+fn world_interfaces(
+    wit_dir: &Path,
+    world: Option<&str>,
+) -> Result<(BTreeSet<String>, BTreeSet<String>)> {
+    if !wit_dir.is_dir() {
+        return Ok(Default::default());
+    }
+
+    let mut resolve = Resolve::new();
+    let (packages, _) = resolve.push_dir(wit_dir)?;
+    let world = resolve.select_world(&packages, world)?;
+
+    let interfaces = |items: &mut dyn Iterator<Item = &WorldItem>| -> BTreeSet<String> {
+        items
+            .filter_map(|item| match item {
+                WorldItem::Interface { id, .. } => resolve.id_of(*id),
+                _ => None,
+            })
+            .collect()
+    };
+
+    let world = &resolve.worlds[world];
+    Ok((
+        interfaces(&mut world.imports.values()),
+        interfaces(&mut world.exports.values()),
+    ))
+}
+
+/// Groups members into levels whose dependencies all sit in earlier levels.
+fn build_levels(members: &[Member], dependencies: &[Vec<usize>]) -> Result<Vec<Vec<usize>>> {
+    let mut placed = vec![false; members.len()];
+    let mut levels = Vec::new();
+
+    while placed.contains(&false) {
+        let level: Vec<usize> = (0..members.len())
+            .filter(|&i| !placed[i] && dependencies[i].iter().all(|&d| placed[d]))
+            .collect();
+
+        if level.is_empty() {
+            let cycle: Vec<&str> = (0..members.len())
+                .filter(|&i| !placed[i])
+                .map(|i| members[i].name.as_str())
+                .collect();
+            return Err(anyhow!(
+                "Workspace members import each other's interfaces in a cycle: {}",
+                cycle.join(", ")
+            ));
+        }
+
+        for &i in &level {
+            placed[i] = true;
+        }
+        levels.push(level);
+    }
+
+    Ok(levels)
+}
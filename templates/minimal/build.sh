@@ -4,5 +4,5 @@ echo "✓ Module embedded in HTML - no build needed!"
 echo "📦 Total size: < 12KB"
 echo ""
 echo "To run the demo:"
-echo "  python3 -m http.server"
+echo "  wasm-wizard dev"
 echo "  Open http://localhost:8000 in your browser"
\ No newline at end of file
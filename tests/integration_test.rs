@@ -1,8 +1,53 @@
 use assert_cmd::Command;
 use predicates::prelude::*;
 use std::fs;
+use std::path::{Path, PathBuf};
 use tempfile::TempDir;
 
+/// Builds a component implementing `world` from `wit` with a dummy core module.
+fn write_component(dir: &Path, file_name: &str, wit: &str, world: &str) -> PathBuf {
+    let mut resolve = wit_parser::Resolve::new();
+    let packages = resolve.push_str("world.wit", wit).unwrap();
+    let world = resolve.select_world(&packages, Some(world)).unwrap();
+
+    let mut module = wit_component::dummy_module(&resolve, world);
+    wit_component::embed_component_metadata(
+        &mut module,
+        &resolve,
+        world,
+        wit_component::StringEncoding::UTF8,
+    )
+    .unwrap();
+
+    let component = wit_component::ComponentEncoder::default()
+        .module(&module)
+        .unwrap()
+        .validate(true)
+        .encode()
+        .unwrap();
+
+    let path = dir.join(file_name);
+    fs::write(&path, component).unwrap();
+    path
+}
+
+const MATH_WIT: &str = r#"
+package test:math;
+
+interface adder {
+    add: func(a: u32, b: u32) -> u32;
+}
+
+world provider {
+    export adder;
+}
+
+world consumer {
+    import adder;
+    export run: func() -> u32;
+}
+"#;
+
 #[test]
 fn test_help_command() {
     let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
@@ -96,3 +141,1722 @@ fn test_install_command_list() {
         .success()
         .stdout(predicate::str::contains("Available tools"));
 }
+
+#[test]
+fn test_build_rejects_unknown_config_key() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("wasm-wizard.toml"),
+        "[project]\nname = \"demo\"\n\n[build]\ntargett = \"wasm32-wasip1\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .env("WASM_WIZARD_CACHE_DIR", cache_dir.path())
+        .arg("build");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("wasm-wizard.toml"))
+        .stderr(predicate::str::contains("line 5"))
+        .stderr(predicate::str::contains("unknown field `targett`"));
+}
+
+#[test]
+fn test_build_rejects_wrong_config_type() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("wasm-wizard.toml"),
+        "[project]\nname = \"demo\"\n\n[optimize]\nlevel = \"high\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .env("WASM_WIZARD_CACHE_DIR", cache_dir.path())
+        .arg("build");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("line 5"))
+        .stderr(predicate::str::contains("invalid type"));
+}
+
+#[test]
+fn test_build_rejects_unsupported_target() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("wasm-wizard.toml"),
+        "[project]\nname = \"demo\"\n\n[build]\ntarget = \"wasm64-unknown-unknown\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .env("WASM_WIZARD_CACHE_DIR", cache_dir.path())
+        .arg("build");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("`build.target` must be one of"))
+        .stderr(predicate::str::contains("wasm32-wasip2"))
+        .stderr(predicate::str::contains("wasm64-unknown-unknown"));
+}
+
+#[test]
+fn test_build_rejects_componentize_js_tool() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("wasm-wizard.toml"),
+        "[project]\nname = \"demo\"\n\n[build]\ntool = \"componentize-js\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .env("WASM_WIZARD_CACHE_DIR", cache_dir.path())
+        .arg("build");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("use `tool = \"npm\"`"));
+}
+
+#[test]
+fn test_compose_satisfies_imports_in_process() {
+    let temp_dir = TempDir::new().unwrap();
+    let consumer = write_component(temp_dir.path(), "consumer.wasm", MATH_WIT, "consumer");
+    let provider = write_component(temp_dir.path(), "provider.wasm", MATH_WIT, "provider");
+    let output = temp_dir.path().join("composed.wasm");
+
+    // An empty PATH hides the wasm-compose CLI, so composition runs in-process
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.env("PATH", "")
+        .arg("compose")
+        .arg(&consumer)
+        .arg(&provider)
+        .arg("--output")
+        .arg(&output);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("In-process composition completed"));
+
+    // The adder import is satisfied by the provider, so nothing is left to import
+    let composed = fs::read(&output).unwrap();
+    let decoded = wit_component::decode(&composed).unwrap();
+    let resolve = decoded.resolve();
+    let world = &resolve.worlds[resolve.select_world(decoded.packages(), None).unwrap()];
+    assert!(world.imports.is_empty());
+    assert_eq!(world.exports.len(), 1);
+}
+
+#[test]
+fn test_compose_manual_connections_from_config() {
+    let temp_dir = TempDir::new().unwrap();
+    write_component(temp_dir.path(), "consumer.wasm", MATH_WIT, "consumer");
+    write_component(temp_dir.path(), "provider.wasm", MATH_WIT, "provider");
+    let config = temp_dir.path().join("compose.toml");
+    fs::write(
+        &config,
+        r#"
+[[components]]
+name = "app"
+path = "consumer.wasm"
+
+[[components]]
+name = "math"
+path = "provider.wasm"
+
+[linking]
+strategy = "manual"
+
+[[linking.connections]]
+from = "math"
+to = "app"
+export = "test:math/adder"
+import = "test:math/adder"
+"#,
+    )
+    .unwrap();
+    let output = temp_dir.path().join("composed.wasm");
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.arg("compose")
+        .arg("consumer.wasm")
+        .arg("--config")
+        .arg(&config)
+        .arg("--output")
+        .arg(&output);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("manual linking"));
+
+    let composed = fs::read(&output).unwrap();
+    let decoded = wit_component::decode(&composed).unwrap();
+    let resolve = decoded.resolve();
+    let world = &resolve.worlds[resolve.select_world(decoded.packages(), None).unwrap()];
+    assert!(world.imports.is_empty());
+}
+
+#[test]
+fn test_compose_manual_reports_unconnected_imports() {
+    let temp_dir = TempDir::new().unwrap();
+    write_component(temp_dir.path(), "consumer.wasm", MATH_WIT, "consumer");
+    write_component(temp_dir.path(), "provider.wasm", MATH_WIT, "provider");
+    let config = temp_dir.path().join("compose.toml");
+    fs::write(
+        &config,
+        r#"
+[[components]]
+name = "app"
+path = "consumer.wasm"
+
+[[components]]
+name = "math"
+path = "provider.wasm"
+
+[linking]
+strategy = "manual"
+"#,
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.env("PATH", "")
+        .arg("compose")
+        .arg("consumer.wasm")
+        .arg("--config")
+        .arg(&config)
+        .arg("--output")
+        .arg(temp_dir.path().join("composed.wasm"));
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Imports without a connection"))
+        .stderr(predicate::str::contains("app imports 'test:math/adder'"));
+}
+
+#[test]
+fn test_compose_auto_counts_explicit_connections_as_used() {
+    let temp_dir = TempDir::new().unwrap();
+    write_component(temp_dir.path(), "consumer.wasm", MATH_WIT, "consumer");
+    write_component(temp_dir.path(), "provider.wasm", MATH_WIT, "provider");
+    let config = temp_dir.path().join("compose.toml");
+    fs::write(
+        &config,
+        r#"
+[[components]]
+name = "app"
+path = "consumer.wasm"
+
+[[components]]
+name = "math"
+path = "provider.wasm"
+
+[linking]
+strategy = "auto"
+
+[[linking.connections]]
+from = "math"
+to = "app"
+export = "test:math/adder"
+import = "test:math/adder"
+"#,
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.env("PATH", "")
+        .arg("compose")
+        .arg("consumer.wasm")
+        .arg("--config")
+        .arg(&config)
+        .arg("--output")
+        .arg(temp_dir.path().join("composed.wasm"));
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Unused exports").not());
+}
+
+#[test]
+fn test_compose_auto_reports_ambiguous_providers() {
+    let temp_dir = TempDir::new().unwrap();
+    let consumer = write_component(temp_dir.path(), "consumer.wasm", MATH_WIT, "consumer");
+    let first = write_component(temp_dir.path(), "first.wasm", MATH_WIT, "provider");
+    let second = write_component(temp_dir.path(), "second.wasm", MATH_WIT, "provider");
+    let config = temp_dir.path().join("compose.toml");
+    fs::write(&config, "[linking]\nstrategy = \"auto\"\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.arg("compose")
+        .arg(&consumer)
+        .arg(&first)
+        .arg(&second)
+        .arg("--config")
+        .arg(&config)
+        .arg("--output")
+        .arg(temp_dir.path().join("composed.wasm"));
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Ambiguous providers"))
+        .stderr(predicate::str::contains(
+            "'test:math/adder' imported by consumer is exported by first, second",
+        ))
+        .stderr(predicate::str::contains("Unused exports"));
+}
+
+#[test]
+fn test_analyze_prints_component_world() {
+    let temp_dir = TempDir::new().unwrap();
+    let wit = r#"
+package test:shapes;
+
+interface geometry {
+    record point { x: f64, y: f64 }
+
+    resource canvas {
+        constructor(width: u32, height: u32);
+        draw: func(points: list<point>) -> result<u32, string>;
+    }
+
+    area: func(points: list<point>) -> option<f64>;
+}
+
+world app {
+    import geometry;
+    export render: func(name: string) -> list<u8>;
+}
+"#;
+    let component = write_component(temp_dir.path(), "app.wasm", wit, "app");
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.arg("analyze").arg(&component);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Kind: component"))
+        .stdout(predicate::str::contains("interface test:shapes/geometry"))
+        .stdout(predicate::str::contains("record point { x: f64, y: f64 }"))
+        .stdout(predicate::str::contains("resource canvas"))
+        .stdout(predicate::str::contains(
+            "constructor(width: u32, height: u32)",
+        ))
+        .stdout(predicate::str::contains(
+            "draw: func(points: list<point>) -> result<u32, string>",
+        ))
+        .stdout(predicate::str::contains(
+            "area: func(points: list<point>) -> option<f64>",
+        ))
+        .stdout(predicate::str::contains(
+            "render: func(name: string) -> list<u8>",
+        ))
+        .stdout(predicate::str::contains("Core Modules (3):"));
+}
+
+#[test]
+fn test_analyze_json_output() {
+    let temp_dir = TempDir::new().unwrap();
+    let component = write_component(temp_dir.path(), "consumer.wasm", MATH_WIT, "consumer");
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.arg("analyze")
+        .arg(&component)
+        .arg("--format")
+        .arg("json");
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let info: serde_json::Value = serde_json::from_slice(&output).unwrap();
+
+    assert_eq!(info["schema_version"], 1);
+    assert_eq!(info["kind"], "component");
+    assert_eq!(info["size"], fs::metadata(&component).unwrap().len());
+    assert_eq!(info["imports"][0]["name"], "test:math/adder");
+    assert_eq!(info["imports"][0]["kind"], "instance");
+    assert_eq!(info["exports"][0]["name"], "run");
+    assert!(!info["modules"].as_array().unwrap().is_empty());
+    assert_eq!(
+        info["world"]["exports"][0]["functions"][0]["signature"],
+        "func() -> u32"
+    );
+}
+
+#[test]
+fn test_analyze_detailed_size_breakdown() {
+    let temp_dir = TempDir::new().unwrap();
+    let module = temp_dir.path().join("profile.wasm");
+    let wasm = wat::parse_str(
+        r#"
+        (module
+          (import "env" "log" (func $log (param i32)))
+          (memory 1)
+          (data (i32.const 0) "a fairly long active data segment")
+          (data "passive")
+          (func $helper (param i32) (result i32)
+            local.get 0 i32.const 1 i32.add i32.const 2 i32.mul)
+          (func $big (result i32)
+            i32.const 1 call $helper i32.const 2 call $helper i32.add
+            i32.const 3 call $helper i32.add i32.const 4 call $helper i32.add)
+          (func $run (export "run") (result i32)
+            call $big i32.const 0 call $log)
+          (func $dead i32.const 5 drop))
+        "#,
+    )
+    .unwrap();
+    fs::write(&module, wasm).unwrap();
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.arg("analyze").arg(&module).arg("--detailed");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Largest Functions (4 of 4)"))
+        .stdout(predicate::str::contains("big (kept alive by run)"))
+        .stdout(predicate::str::contains("dead (unreachable)"))
+        .stdout(predicate::str::contains("custom:name"))
+        .stdout(predicate::str::contains("Data Segments (2 of 2)"));
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.arg("analyze")
+        .arg(&module)
+        .args(["--detailed", "--top", "1", "--format", "json"]);
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let info: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let profile = &info["profiles"][0];
+    let big = &profile["functions"][0];
+    assert_eq!(profile["functions"].as_array().unwrap().len(), 1);
+    assert_eq!(big["name"], "big");
+    assert_eq!(big["retained_by"], "run");
+    // `helper` is only reachable through `big`, so it counts towards its retained size
+    assert!(big["retained_size"].as_u64() > big["size"].as_u64());
+    assert_eq!(profile["data_segment_count"], 2);
+}
+
+#[test]
+fn test_check_validates_components() {
+    let temp_dir = TempDir::new().unwrap();
+    let component = write_component(temp_dir.path(), "provider.wasm", MATH_WIT, "provider");
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.arg("check").arg(&component);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("provider.wasm (component"))
+        .stdout(predicate::str::contains("All components are valid"));
+}
+
+#[test]
+fn test_check_all_reports_invalid_build_outputs() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("wasm-wizard.toml"),
+        "[project]\nname = \"demo\"\n",
+    )
+    .unwrap();
+    let release_dir = temp_dir.path().join("target/wasm32-wasip1/release");
+    fs::create_dir_all(&release_dir).unwrap();
+    write_component(&release_dir, "good.wasm", MATH_WIT, "provider");
+    // Parses fine but the function body leaves nothing on the stack for its result
+    let invalid = wat::parse_str("(module (func (result i32)))").unwrap();
+    fs::write(release_dir.join("bad.wasm"), invalid).unwrap();
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.current_dir(temp_dir.path()).args(["check", "--all"]);
+
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("good.wasm (component"))
+        .stdout(predicate::str::contains("bad.wasm"))
+        .stdout(predicate::str::contains("at offset 0x"))
+        .stdout(predicate::str::contains("type mismatch"))
+        .stderr(predicate::str::contains(
+            "1 of 2 component(s) failed validation",
+        ));
+}
+
+#[test]
+fn test_check_reports_project_problems() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("wasm-wizard.toml"),
+        "[project]\nname = \"demo\"\n\n[toolchain]\nrequired_tools = [\"wasm-wizard-missing-tool\"]\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("Cargo.toml"),
+        "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n\n[package.metadata.component]\npackage = \"demo:component\"\n",
+    )
+    .unwrap();
+    fs::create_dir(temp_dir.path().join("wit")).unwrap();
+    fs::write(temp_dir.path().join("wit/world.wit"), MATH_WIT).unwrap();
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.current_dir(temp_dir.path()).arg("check");
+
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("wasm-wizard.toml is valid"))
+        .stdout(predicate::str::contains("wit/ parses as package test:math"))
+        .stdout(predicate::str::contains(
+            "package \"demo:component\" does not match WIT package \"test:math\"",
+        ))
+        .stdout(predicate::str::contains(
+            "Required tool wasm-wizard-missing-tool is not on PATH",
+        ))
+        .stderr(predicate::str::contains("project problem(s) found"));
+}
+
+#[test]
+fn test_check_uses_the_builders_rust_target() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("wasm-wizard.toml"),
+        "[project]\nname = \"crypto\"\n\n[build]\ntool = \"wasm-pack\"\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("Cargo.toml"),
+        "[package]\nname = \"crypto\"\nversion = \"0.1.0\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.current_dir(temp_dir.path()).arg("check");
+
+    let output = cmd.output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("wasm32-unknown-unknown"), "{stdout}");
+    assert!(!stdout.contains("wasm32-wasip1"), "{stdout}");
+}
+
+#[test]
+fn test_check_all_reports_invalid_config() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("wasm-wizard.toml"),
+        "[project]\nname = \"demo\"\nunknown_key = 1\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.current_dir(temp_dir.path()).args(["check", "--all"]);
+
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("unknown field `unknown_key`"))
+        .stdout(predicate::str::contains("Cannot locate the build outputs"))
+        .stderr(predicate::str::contains("project problem(s) found"));
+}
+
+#[test]
+fn test_check_fix_generates_missing_config() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("package.json"),
+        r#"{ "name": "greeter", "version": "1.0.0" }"#,
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.current_dir(temp_dir.path()).arg("check");
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("wasm-wizard.toml not found"));
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.current_dir(temp_dir.path()).args(["check", "--fix"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Generated wasm-wizard.toml"));
+
+    let config = fs::read_to_string(temp_dir.path().join("wasm-wizard.toml")).unwrap();
+    assert!(config.contains("name = \"greeter\""));
+    assert!(config.contains("language = \"javascript\""));
+}
+
+/// Sends a bare HTTP/1.1 GET and returns the raw response.
+fn http_get(port: u16, path: &str) -> String {
+    use std::io::{Read, Write};
+
+    let mut stream = std::net::TcpStream::connect(("127.0.0.1", port)).unwrap();
+    write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).unwrap();
+    String::from_utf8_lossy(&response).into_owned()
+}
+
+/// Starts `wasm-wizard dev` on a free port in `dir` and waits until it accepts connections.
+fn spawn_dev_server(dir: &Path, args: &[&str], cache_dir: &Path) -> (std::process::Child, u16) {
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let server = std::process::Command::new(assert_cmd::cargo::cargo_bin("wasm-wizard"))
+        .current_dir(dir)
+        .env("WASM_WIZARD_CACHE_DIR", cache_dir)
+        .args(["dev", "--host", "127.0.0.1", "--port", &port.to_string()])
+        .args(args)
+        .stdout(std::process::Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let started = std::time::Instant::now();
+    while std::net::TcpStream::connect(("127.0.0.1", port)).is_err() {
+        assert!(started.elapsed().as_secs() < 10, "dev server did not start");
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+
+    (server, port)
+}
+
+/// Opens the hot reload event stream and reads until `needle` shows up.
+fn wait_for_event(port: u16, needle: &str, trigger: impl FnOnce()) -> String {
+    use std::io::{Read, Write};
+
+    let mut stream = std::net::TcpStream::connect(("127.0.0.1", port)).unwrap();
+    stream
+        .set_read_timeout(Some(std::time::Duration::from_secs(10)))
+        .unwrap();
+    write!(
+        stream,
+        "GET /__wasm-wizard/events HTTP/1.1\r\nHost: localhost\r\n\r\n"
+    )
+    .unwrap();
+
+    let mut received = String::new();
+    let mut buffer = [0; 1024];
+    let mut trigger = Some(trigger);
+    while !received.contains(needle) {
+        let read = stream.read(&mut buffer).unwrap();
+        assert!(read > 0, "event stream closed: {}", received);
+        received.push_str(&String::from_utf8_lossy(&buffer[..read]));
+        if received.contains("\r\n\r\n") {
+            if let Some(trigger) = trigger.take() {
+                trigger();
+            }
+        }
+    }
+    received
+}
+
+#[test]
+fn test_dev_serves_project_files() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("wasm-wizard.toml"),
+        "[project]\nname = \"demo\"\n\n[files]\nentry = \"demo.html\"\n",
+    )
+    .unwrap();
+    fs::write(temp_dir.path().join("demo.html"), "<h1>demo page</h1>").unwrap();
+    fs::write(temp_dir.path().join(".env"), "SECRET=1").unwrap();
+    fs::create_dir(temp_dir.path().join(".git")).unwrap();
+    fs::write(temp_dir.path().join(".git/config"), "[core]").unwrap();
+    let release_dir = temp_dir.path().join("target/wasm32-wasip1/release");
+    fs::create_dir_all(&release_dir).unwrap();
+    write_component(&release_dir, "demo.wasm", MATH_WIT, "provider");
+
+    let (mut server, port) = spawn_dev_server(temp_dir.path(), &[], cache_dir.path());
+
+    let page = http_get(port, "/");
+    let wasm = http_get(port, "/demo.wasm");
+    let missing = http_get(port, "/../wasm-wizard.toml");
+    let config = http_get(port, "/wasm-wizard.toml");
+    let dotfile = http_get(port, "/.env");
+    let git = http_get(port, "/.git/config");
+    server.kill().unwrap();
+    server.wait().unwrap();
+
+    assert!(page.starts_with("HTTP/1.1 200 OK"));
+    assert!(page.contains("Content-Type: text/html"));
+    assert!(page.ends_with("<h1>demo page</h1>"));
+    assert!(wasm.starts_with("HTTP/1.1 200 OK"));
+    assert!(wasm.contains("Content-Type: application/wasm"));
+    assert!(missing.starts_with("HTTP/1.1 404 Not Found"));
+    for hidden in [config, dotfile, git] {
+        assert!(hidden.starts_with("HTTP/1.1 404 Not Found"), "{hidden}");
+    }
+}
+
+#[test]
+fn test_dev_hot_reload_pushes_reload_events() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("wasm-wizard.toml"),
+        "[project]\nname = \"demo\"\n\n[dev]\nwatch_paths = [\"*.html\"]\n",
+    )
+    .unwrap();
+    let page = temp_dir.path().join("demo.html");
+    fs::write(&page, "<html><body><h1>v1</h1></body></html>").unwrap();
+
+    let (mut server, port) = spawn_dev_server(temp_dir.path(), &["--hot-reload"], cache_dir.path());
+    let html = http_get(port, "/");
+    let events = wait_for_event(port, "event: reload", || {
+        fs::write(&page, "<html><body><h1>v2</h1></body></html>").unwrap();
+    });
+    server.kill().unwrap();
+    server.wait().unwrap();
+
+    assert!(html.contains("new EventSource(\"/__wasm-wizard/events\")"));
+    assert!(html.find("EventSource").unwrap() < html.find("</body>").unwrap());
+    assert!(events.contains("Content-Type: text/event-stream"));
+}
+
+#[test]
+fn test_dev_hot_reload_reports_build_errors() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("wasm-wizard.toml"),
+        "[project]\nname = \"demo\"\n",
+    )
+    .unwrap();
+    // No `build:wasm` script, so the initial build fails
+    fs::write(
+        temp_dir.path().join("package.json"),
+        r#"{ "name": "demo" }"#,
+    )
+    .unwrap();
+
+    let (mut server, port) = spawn_dev_server(temp_dir.path(), &["--hot-reload"], cache_dir.path());
+    let events = wait_for_event(port, "event: build-error", || {});
+    server.kill().unwrap();
+    server.wait().unwrap();
+
+    assert!(events.contains("data: {\"message\":"));
+}
+
+#[test]
+fn test_build_watch_rebuilds_on_change() {
+    use std::io::BufRead;
+
+    let temp_dir = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("wasm-wizard.toml"),
+        "[project]\nname = \"demo\"\n",
+    )
+    .unwrap();
+    // No `build:wasm` script, so every iteration fails quickly without a toolchain
+    fs::write(
+        temp_dir.path().join("package.json"),
+        r#"{ "name": "demo" }"#,
+    )
+    .unwrap();
+    fs::create_dir(temp_dir.path().join("src")).unwrap();
+    fs::write(temp_dir.path().join("src/index.js"), "export {};").unwrap();
+
+    let mut build = std::process::Command::new(assert_cmd::cargo::cargo_bin("wasm-wizard"))
+        .current_dir(temp_dir.path())
+        .env("WASM_WIZARD_CACHE_DIR", cache_dir.path())
+        .args(["build", "--watch"])
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let (lines, received) = std::sync::mpsc::channel();
+    let stdout = build.stdout.take().unwrap();
+    std::thread::spawn(move || {
+        for line in std::io::BufReader::new(stdout).lines() {
+            if lines.send(line.unwrap()).is_err() {
+                break;
+            }
+        }
+    });
+    let wait_for = |needle: &str| loop {
+        let line = received
+            .recv_timeout(std::time::Duration::from_secs(10))
+            .unwrap_or_else(|_| panic!("timed out waiting for {:?}", needle));
+        if line.contains(needle) {
+            break;
+        }
+    };
+
+    wait_for("Watching src/");
+    fs::write(temp_dir.path().join("src/index.js"), "export const x = 1;").unwrap();
+    wait_for("1 file(s) changed, rebuilding");
+    wait_for("Build failed after");
+
+    build.kill().unwrap();
+    build.wait().unwrap();
+}
+
+/// Whether `rustup` has `target` installed, for tests that compile to it.
+fn rust_target_installed(target: &str) -> bool {
+    std::process::Command::new("rustup")
+        .args(["target", "list", "--installed"])
+        .output()
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .any(|l| l == target)
+        })
+        .unwrap_or(false)
+}
+
+/// Writes a dependency-free Rust library exporting `add` and a WIT world describing it.
+fn write_rust_project(dir: &Path, config: &str) {
+    fs::write(dir.join("wasm-wizard.toml"), config).unwrap();
+    fs::write(
+        dir.join("Cargo.toml"),
+        "[package]\nname = \"adder\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[lib]\ncrate-type = [\"cdylib\"]\n\n[workspace]\n",
+    )
+    .unwrap();
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(
+        dir.join("src/lib.rs"),
+        "#[no_mangle]\npub extern \"C\" fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n",
+    )
+    .unwrap();
+    fs::create_dir_all(dir.join("wit")).unwrap();
+    fs::write(
+        dir.join("wit/world.wit"),
+        "package test:adder;\n\nworld adder {\n    export add: func(a: s32, b: s32) -> s32;\n}\n",
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_build_produces_component() {
+    if !rust_target_installed("wasm32-wasip1") {
+        return;
+    }
+
+    let temp_dir = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    write_rust_project(temp_dir.path(), "[project]\nname = \"adder\"\n");
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .env("WASM_WIZARD_CACHE_DIR", cache_dir.path())
+        .env_remove("CARGO_TARGET_DIR")
+        .arg("build");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("adder.component.wasm"));
+
+    let release_dir = temp_dir.path().join("target/wasm32-wasip1/release");
+    let component = fs::read(release_dir.join("adder.component.wasm")).unwrap();
+    assert!(wasmparser::Parser::is_component(&component));
+    wasmparser::Validator::new_with_features(
+        wasmparser::WasmFeatures::default() | wasmparser::WasmFeatures::COMPONENT_MODEL,
+    )
+    .validate_all(&component)
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.arg("analyze")
+        .arg(release_dir.join("adder.component.wasm"));
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("add: func(a: s32, b: s32) -> s32"));
+
+    // Opting out keeps the plain core module
+    fs::remove_file(release_dir.join("adder.component.wasm")).unwrap();
+    fs::write(
+        temp_dir.path().join("wasm-wizard.toml"),
+        "[project]\nname = \"adder\"\n\n[build]\noutput_format = \"module\"\n",
+    )
+    .unwrap();
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .env("WASM_WIZARD_CACHE_DIR", cache_dir.path())
+        .env_remove("CARGO_TARGET_DIR")
+        .arg("build");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("adder.wasm"));
+    assert!(!release_dir.join("adder.component.wasm").exists());
+}
+
+#[test]
+fn test_build_copies_cargo_artifacts_to_output_dir() {
+    if !rust_target_installed("wasm32-wasip1") {
+        return;
+    }
+
+    let temp_dir = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    write_rust_project(temp_dir.path(), "[project]\nname = \"adder\"\n");
+
+    // A stray module sorting before the real one must not be picked up
+    let release_dir = temp_dir.path().join("target/wasm32-wasip1/release");
+    fs::create_dir_all(&release_dir).unwrap();
+    fs::write(release_dir.join("aaa.wasm"), b"not wasm").unwrap();
+
+    let output_dir = temp_dir.path().join("out");
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .env("WASM_WIZARD_CACHE_DIR", cache_dir.path())
+        .env_remove("CARGO_TARGET_DIR")
+        .arg("build")
+        .arg("--output")
+        .arg(&output_dir);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("out/adder.component.wasm"));
+
+    let outputs: Vec<_> = fs::read_dir(&output_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(outputs, ["adder.component.wasm"]);
+
+    let component = fs::read(output_dir.join("adder.component.wasm")).unwrap();
+    assert!(wasmparser::Parser::is_component(&component));
+}
+
+/// Writes a workspace where `app` imports the `test:kv/store` interface that
+/// `storage` exports.
+fn write_workspace(dir: &Path) {
+    fs::write(
+        dir.join("wasm-wizard.toml"),
+        "[project]\nname = \"ws\"\n\n[workspace]\nmembers = [\"app\", \"storage\"]\n",
+    )
+    .unwrap();
+
+    let store = "package test:kv;\n\ninterface store {\n    get: func() -> s32;\n}\n";
+    for (member, export) in [("app", "run"), ("storage", "test:kv/store#get")] {
+        let member_dir = dir.join(member);
+        fs::create_dir_all(member_dir.join("src")).unwrap();
+        fs::write(
+            member_dir.join("Cargo.toml"),
+            format!("[package]\nname = \"{member}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[lib]\ncrate-type = [\"cdylib\"]\n\n[workspace]\n"),
+        )
+        .unwrap();
+        fs::write(
+            member_dir.join("src/lib.rs"),
+            format!(
+                "#[export_name = \"{export}\"]\npub extern \"C\" fn f() -> i32 {{\n    1\n}}\n"
+            ),
+        )
+        .unwrap();
+    }
+
+    fs::create_dir_all(dir.join("storage/wit")).unwrap();
+    fs::write(
+        dir.join("storage/wit/world.wit"),
+        format!("{store}\nworld storage {{\n    export store;\n}}\n"),
+    )
+    .unwrap();
+
+    fs::create_dir_all(dir.join("app/wit/deps/kv")).unwrap();
+    fs::write(dir.join("app/wit/deps/kv/store.wit"), store).unwrap();
+    fs::write(
+        dir.join("app/wit/world.wit"),
+        "package test:app;\n\nworld app {\n    import test:kv/store;\n    export run: func() -> s32;\n}\n",
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_build_workspace_in_dependency_order() {
+    if !rust_target_installed("wasm32-wasip1") {
+        return;
+    }
+
+    let temp_dir = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    write_workspace(temp_dir.path());
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .env("WASM_WIZARD_CACHE_DIR", cache_dir.path())
+        .env_remove("CARGO_TARGET_DIR")
+        .arg("build")
+        .arg("--output")
+        .arg("out");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8(output).unwrap();
+
+    // storage exports what app imports, so it is built first
+    let storage = stdout.find("out/storage/storage.component.wasm");
+    let app = stdout.find("out/app/app.component.wasm");
+    assert!(storage.unwrap() < app.unwrap(), "{}", stdout);
+    // Each member copies into its own directory under --output
+    assert!(temp_dir
+        .path()
+        .join("out/storage/storage.component.wasm")
+        .is_file());
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.current_dir(temp_dir.path()).arg("analyze");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Workspace Summary:"))
+        .stdout(predicate::str::contains("app.component.wasm"));
+}
+
+#[test]
+fn test_workspace_rejects_dependency_cycles() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    write_workspace(temp_dir.path());
+
+    // Make storage import what app exports, closing the cycle
+    fs::write(
+        temp_dir.path().join("app/wit/world.wit"),
+        "package test:app;\n\ninterface api {\n    run: func() -> s32;\n}\n\nworld app {\n    import test:kv/store;\n    export api;\n}\n",
+    )
+    .unwrap();
+    fs::create_dir_all(temp_dir.path().join("storage/wit/deps/app")).unwrap();
+    fs::write(
+        temp_dir.path().join("storage/wit/deps/app/api.wit"),
+        "package test:app;\n\ninterface api {\n    run: func() -> s32;\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("storage/wit/world.wit"),
+        "package test:kv;\n\ninterface store {\n    get: func() -> s32;\n}\n\nworld storage {\n    import test:app/api;\n    export store;\n}\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .env("WASM_WIZARD_CACHE_DIR", cache_dir.path())
+        .arg("build");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("in a cycle: app, storage"));
+}
+
+#[test]
+fn test_build_reuses_cached_artifacts() {
+    if !rust_target_installed("wasm32-wasip1") {
+        return;
+    }
+
+    let temp_dir = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    write_rust_project(temp_dir.path(), "[project]\nname = \"adder\"\n");
+    // Cargo.lock is an input, so create it before the first build does
+    assert!(std::process::Command::new("cargo")
+        .arg("generate-lockfile")
+        .current_dir(temp_dir.path())
+        .status()
+        .unwrap()
+        .success());
+
+    let build = || {
+        let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+        cmd.current_dir(temp_dir.path())
+            .env_remove("CARGO_TARGET_DIR")
+            .env("WASM_WIZARD_CACHE_DIR", cache_dir.path())
+            .arg("build");
+        cmd
+    };
+
+    build()
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("from cache").not());
+
+    // Unchanged inputs restore the artifact without running cargo
+    fs::remove_dir_all(temp_dir.path().join("target")).unwrap();
+    build().assert().success().stdout(predicate::str::contains(
+        "restored 1 artifact(s) from cache",
+    ));
+    assert!(temp_dir
+        .path()
+        .join("target/wasm32-wasip1/release/adder.component.wasm")
+        .is_file());
+
+    build()
+        .arg("--no-cache")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("from cache").not());
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.env("WASM_WIZARD_CACHE_DIR", cache_dir.path())
+        .args(["cache", "clean"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Removed 2 cached file(s)"));
+
+    build()
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("from cache").not());
+}
+
+#[test]
+fn test_build_runs_post_hooks_on_every_build() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    let project_path = temp_dir.path().join("demo");
+    fs::create_dir_all(&project_path).unwrap();
+    fs::write(
+        project_path.join("module.wasm"),
+        wat::parse_str("(module (func (export \"run\")))").unwrap(),
+    )
+    .unwrap();
+    // The log lives outside the project, so it does not change the inputs
+    fs::write(
+        project_path.join("wasm-wizard.toml"),
+        "[project]\nname = \"demo\"\n\n[build]\ncommand = \"mkdir -p dist && cp module.wasm dist/demo.wasm\"\noutput_format = \"module\"\n\n[hooks]\npost_build = [\"echo \\\"post $(basename $WASM_WIZARD_ARTIFACT)\\\" >> ../hooks.log\"]\n",
+    )
+    .unwrap();
+
+    for _ in 0..2 {
+        let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+        cmd.current_dir(&project_path)
+            .env("WASM_WIZARD_CACHE_DIR", cache_dir.path())
+            .arg("build");
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("from cache").not());
+    }
+
+    let log = fs::read_to_string(temp_dir.path().join("hooks.log")).unwrap();
+    assert_eq!(log, "post demo.wasm\npost demo.wasm\n");
+}
+
+#[test]
+fn test_build_cache_tracks_path_dependencies() {
+    if !rust_target_installed("wasm32-wasip1") {
+        return;
+    }
+
+    let temp_dir = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    let app = temp_dir.path().join("app");
+    let lib = temp_dir.path().join("lib");
+    fs::create_dir_all(lib.join("src")).unwrap();
+    fs::write(
+        lib.join("Cargo.toml"),
+        "[package]\nname = \"offset\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    )
+    .unwrap();
+    fs::write(lib.join("src/lib.rs"), "pub const OFFSET: i32 = 0;\n").unwrap();
+    fs::create_dir_all(&app).unwrap();
+    write_rust_project(&app, "[project]\nname = \"adder\"\n");
+    let mut manifest = fs::read_to_string(app.join("Cargo.toml")).unwrap();
+    manifest.push_str("\n[dependencies]\noffset = { path = \"../lib\" }\n");
+    fs::write(app.join("Cargo.toml"), manifest).unwrap();
+
+    let build = || {
+        let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+        cmd.current_dir(&app)
+            .env_remove("CARGO_TARGET_DIR")
+            .env("WASM_WIZARD_CACHE_DIR", cache_dir.path())
+            .args(["build", "--output", "out"]);
+        cmd
+    };
+
+    build()
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("from cache").not());
+
+    // The copies in --output are not inputs
+    build().assert().success().stdout(predicate::str::contains(
+        "restored 1 artifact(s) from cache",
+    ));
+
+    // Neither is the dependency outside the project, but cargo rebuilds it
+    fs::write(lib.join("src/lib.rs"), "pub const OFFSET: i32 = 1;\n").unwrap();
+    build()
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("from cache").not());
+}
+
+#[test]
+fn test_build_profile_selects_cargo_profile_and_output_name() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    write_rust_project(
+        temp_dir.path(),
+        "[project]\nname = \"adder\"\n\n[profile.tiny]\ncargo_profile = \"dev\"\noutput_name = \"{name}-{profile}.wasm\"\n",
+    );
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .env("WASM_WIZARD_CACHE_DIR", cache_dir.path())
+        .arg("build")
+        .arg("--profile")
+        .arg("huge");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Unknown profile \"huge\"; available profiles: dev, release, size, speed, tiny",
+    ));
+
+    if !rust_target_installed("wasm32-wasip1") {
+        return;
+    }
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .env("WASM_WIZARD_CACHE_DIR", cache_dir.path())
+        .env_remove("CARGO_TARGET_DIR")
+        .arg("build")
+        .arg("--profile")
+        .arg("tiny");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("debug/adder-tiny.wasm"));
+
+    let component = fs::read(
+        temp_dir
+            .path()
+            .join("target/wasm32-wasip1/debug/adder-tiny.wasm"),
+    )
+    .unwrap();
+    assert!(wasmparser::Parser::is_component(&component));
+}
+
+#[test]
+fn test_build_runs_hooks() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    write_rust_project(
+        temp_dir.path(),
+        "[project]\nname = \"adder\"\n\n[hooks]\npre_build = [\"echo failing >&2; exit 3\"]\n",
+    );
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .env("WASM_WIZARD_CACHE_DIR", cache_dir.path())
+        .arg("build");
+    cmd.assert().failure().stderr(
+        predicate::str::contains("pre_build hook").and(predicate::str::contains("failing")),
+    );
+
+    if !rust_target_installed("wasm32-wasip1") {
+        return;
+    }
+
+    fs::write(
+        temp_dir.path().join("wasm-wizard.toml"),
+        "[project]\nname = \"adder\"\n\n[hooks]\npre_build = [\"echo \\\"pre $WASM_WIZARD_PROFILE $WASM_WIZARD_TARGET\\\" >> hooks.log\"]\npost_build = [\"echo \\\"post $(basename $WASM_WIZARD_ARTIFACT)\\\" >> hooks.log\"]\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .env("WASM_WIZARD_CACHE_DIR", cache_dir.path())
+        .env_remove("CARGO_TARGET_DIR")
+        .arg("build")
+        .arg("--no-cache");
+    cmd.assert().success();
+
+    let log = fs::read_to_string(temp_dir.path().join("hooks.log")).unwrap();
+    assert_eq!(log, "pre release wasm32-wasip1\npost adder.wasm\n");
+}
+
+#[test]
+fn test_build_runs_configured_command() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    let project_path = temp_dir.path().join("demo");
+
+    // The minimal template's build.sh embeds its module in the page
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.arg("new")
+        .arg("demo")
+        .arg("--template")
+        .arg("minimal")
+        .arg("--path")
+        .arg(&project_path)
+        .arg("--no-git")
+        .arg("--no-install");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.current_dir(&project_path)
+        .env("WASM_WIZARD_CACHE_DIR", cache_dir.path())
+        .arg("build");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("produced no .wasm files"));
+
+    // Modules the command writes to dist/ are picked up as outputs
+    fs::write(
+        project_path.join("module.wasm"),
+        wat::parse_str("(module (func (export \"run\")))").unwrap(),
+    )
+    .unwrap();
+    fs::write(
+        project_path.join("wasm-wizard.toml"),
+        "[project]\nname = \"demo\"\n\n[build]\ncommand = \"mkdir -p dist && cp module.wasm dist/demo-$WASM_WIZARD_PROFILE.wasm\"\noutput_format = \"module\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.current_dir(&project_path)
+        .env("WASM_WIZARD_CACHE_DIR", cache_dir.path())
+        .arg("build")
+        .arg("--no-cache");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("dist/demo-release.wasm"));
+
+    // Modules an earlier build left in dist/ are not outputs of this one
+    fs::write(
+        project_path.join("wasm-wizard.toml"),
+        "[project]\nname = \"demo\"\n\n[build]\ncommand = \"true\"\noutput_format = \"module\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.current_dir(&project_path)
+        .env("WASM_WIZARD_CACHE_DIR", cache_dir.path())
+        .arg("build")
+        .arg("--no-cache");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("produced no .wasm files"))
+        .stdout(predicate::str::contains("demo-release.wasm").not());
+}
+
+#[test]
+fn test_workspace_optimize_skips_profile_outputs() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("wasm-wizard.toml"),
+        "[project]\nname = \"ws\"\n\n[workspace]\nmembers = [\"demo\"]\n",
+    )
+    .unwrap();
+
+    let dist = temp_dir.path().join("demo/dist");
+    fs::create_dir_all(&dist).unwrap();
+    fs::write(
+        temp_dir.path().join("demo/wasm-wizard.toml"),
+        "[project]\nname = \"demo\"\n\n[build]\ncommand = \"true\"\n\n[profile.tiny]\noutput_name = \"{name}-{profile}.wasm\"\n",
+    )
+    .unwrap();
+    let module = wat::parse_str("(module (func (export \"run\")))").unwrap();
+    fs::write(dist.join("demo.wasm"), &module).unwrap();
+    // Written by `build --profile tiny`, so not a toolchain output
+    fs::write(dist.join("demo-tiny.wasm"), &module).unwrap();
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .env("WASM_WIZARD_CACHE_DIR", cache_dir.path())
+        .env("PATH", "")
+        .arg("optimize");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("demo.optimized.wasm"))
+        .stdout(predicate::str::contains("demo-tiny").not());
+    assert!(!dist.join("demo-tiny.optimized.wasm").exists());
+}
+
+#[test]
+fn test_build_times_out_slow_commands() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    write_rust_project(
+        temp_dir.path(),
+        "[project]\nname = \"adder\"\n\n[build]\ntimeout = 1\n\n[hooks]\npre_build = [\"echo generating; sleep 30\"]\n",
+    );
+
+    let started = std::time::Instant::now();
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .env("WASM_WIZARD_CACHE_DIR", cache_dir.path())
+        .arg("build");
+    cmd.assert().failure().stderr(
+        predicate::str::contains("timed out after 1s").and(predicate::str::contains("generating")),
+    );
+    assert!(started.elapsed() < std::time::Duration::from_secs(10));
+}
+
+/// Appends a custom section to a core module or component.
+fn append_custom_section(wasm: &mut Vec<u8>, name: &str, data: &[u8]) {
+    let mut contents = vec![name.len() as u8];
+    contents.extend_from_slice(name.as_bytes());
+    contents.extend_from_slice(data);
+    wasm.push(0);
+    wasm.push(contents.len() as u8);
+    wasm.extend_from_slice(&contents);
+}
+
+/// Raw bytes of every non-custom section, nested ones included, and the
+/// names of the custom sections.
+fn wasm_sections(wasm: &[u8]) -> (Vec<(u8, Vec<u8>)>, Vec<String>) {
+    let (mut sections, mut custom) = (Vec::new(), Vec::new());
+    for payload in wasmparser::Parser::new(0).parse_all(wasm) {
+        match payload.unwrap() {
+            wasmparser::Payload::CustomSection(reader) => custom.push(reader.name().to_string()),
+            // Nested modules and components are compared by their sections
+            wasmparser::Payload::ModuleSection { .. }
+            | wasmparser::Payload::ComponentSection { .. } => {}
+            payload => {
+                if let Some((id, range)) = payload.as_section() {
+                    sections.push((id, wasm[range].to_vec()));
+                }
+            }
+        }
+    }
+    (sections, custom)
+}
+
+#[test]
+fn test_manual_optimizer_only_strips_custom_sections() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+
+    let mut module = wat::parse_str(
+        r#"(module
+            (memory 1)
+            (global $g (mut i32) (i32.const 1))
+            (data (i32.const 0) "hello")
+            (func $add (export "add") (param i32 i32) (result i32)
+                local.get 0
+                local.get 1
+                i32.add)
+            (func (export "bump") global.get $g i32.const 1 i32.add global.set $g)
+            (start 1))"#,
+    )
+    .unwrap();
+    append_custom_section(&mut module, ".debug_info", b"dwarf");
+    append_custom_section(&mut module, "producers", b"\0");
+    append_custom_section(&mut module, "target_features", b"\0");
+
+    let component = wat::parse_str(
+        r#"(component
+            (core module $m
+                (@custom ".debug_line" "lines")
+                (func (export "run") (result i32) i32.const 7))
+            (core instance (instantiate $m)))"#,
+    )
+    .unwrap();
+
+    for (name, wasm) in [("module.wasm", module), ("component.wasm", component)] {
+        let input = temp_dir.path().join(name);
+        let output = temp_dir.path().join(format!("stripped-{name}"));
+        fs::write(&input, &wasm).unwrap();
+
+        // An empty PATH hides wasm-opt, so the manual optimizer runs
+        let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+        cmd.current_dir(temp_dir.path())
+            .env("WASM_WIZARD_CACHE_DIR", cache_dir.path())
+            .env("PATH", "")
+            .arg("optimize")
+            .arg(&input)
+            .arg("--output")
+            .arg(&output)
+            .arg("--no-cache");
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("Manual optimization completed"));
+
+        let stripped = fs::read(&output).unwrap();
+        wasmparser::Validator::new_with_features(
+            wasmparser::WasmFeatures::default() | wasmparser::WasmFeatures::COMPONENT_MODEL,
+        )
+        .validate_all(&stripped)
+        .unwrap();
+
+        let (sections, custom) = wasm_sections(&wasm);
+        let (stripped_sections, stripped_custom) = wasm_sections(&stripped);
+        assert_eq!(stripped_sections, sections, "{name}");
+        assert!(!custom.is_empty(), "{name}");
+        assert!(
+            stripped_custom
+                .iter()
+                .all(|name| !name.starts_with(".debug") && name != "producers"),
+            "{name}: {stripped_custom:?}"
+        );
+        if name == "module.wasm" {
+            assert!(stripped_custom.iter().any(|name| name == "target_features"));
+        }
+    }
+}
+
+#[test]
+fn test_manual_optimizer_removes_unreachable_code() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    let input = temp_dir.path().join("module.wasm");
+    let output = temp_dir.path().join("module.optimized.wasm");
+
+    let module = wat::parse_str(
+        r#"(module
+            (type $unused (func (param f64)))
+            (import "env" "log" (func $log (param i32)))
+            (import "env" "trace" (func $trace (param i64)))
+            (global $unused (mut i32) (i32.const 0))
+            (global $counter (mut i32) (i32.const 0))
+            (table 1 funcref)
+            (elem (i32.const 0) $callback)
+            (func $dead (call $trace (i64.const 1)) (global.set $unused (i32.const 1)))
+            (func $callback (param i32) (call $log (local.get 0)))
+            (func (export "run") (param i32)
+                (global.set $counter (i32.add (global.get $counter) (i32.const 1)))
+                (call_indirect (param i32) (local.get 0) (i32.const 0))))"#,
+    )
+    .unwrap();
+    fs::write(&input, &module).unwrap();
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .env("WASM_WIZARD_CACHE_DIR", cache_dir.path())
+        .env("PATH", "")
+        .arg("optimize")
+        .arg(&input)
+        .arg("--output")
+        .arg(&output)
+        .arg("--level")
+        .arg("1")
+        .arg("--no-cache");
+    cmd.assert().success().stdout(predicate::str::contains(
+        "Removed unreachable 1 function(s), 1 global(s), 1 import(s) and 3 type(s)",
+    ));
+
+    let optimized = fs::read(&output).unwrap();
+    wasmparser::Validator::new().validate_all(&optimized).unwrap();
+
+    let mut imports = Vec::new();
+    let mut exports = Vec::new();
+    let mut functions = 0;
+    let mut names = Vec::new();
+    for payload in wasmparser::Parser::new(0).parse_all(&optimized) {
+        match payload.unwrap() {
+            wasmparser::Payload::CustomSection(reader) => {
+                let wasmparser::KnownCustom::Name(reader) = reader.as_known() else {
+                    continue;
+                };
+                for name in reader {
+                    let (kind, map) = match name.unwrap() {
+                        wasmparser::Name::Function(map) => ("func", map),
+                        wasmparser::Name::Global(map) => ("global", map),
+                        _ => continue,
+                    };
+                    for naming in map {
+                        let naming = naming.unwrap();
+                        names.push(format!("{} {} {}", kind, naming.index, naming.name));
+                    }
+                }
+            }
+            wasmparser::Payload::ImportSection(reader) => {
+                for import in reader {
+                    imports.push(import.unwrap().name.to_string());
+                }
+            }
+            wasmparser::Payload::ExportSection(reader) => {
+                for export in reader {
+                    let export = export.unwrap();
+                    exports.push((export.name.to_string(), export.index));
+                }
+            }
+            wasmparser::Payload::CodeSectionEntry(_) => functions += 1,
+            _ => {}
+        }
+    }
+    assert_eq!(imports, ["log"]);
+    assert_eq!(exports, [("run".to_string(), 2)]);
+    assert_eq!(functions, 2);
+    // The names of the remaining items follow their new indices
+    assert_eq!(names, ["func 0 log", "func 1 callback", "global 0 counter"]);
+}
+
+#[test]
+fn test_optimize_size_tries_strategies_until_target() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    let input = temp_dir.path().join("module.wasm");
+    let output = temp_dir.path().join("module.optimized.wasm");
+
+    let mut module = wat::parse_str(
+        r#"(module
+            (func $unused (result i32) i32.const 1)
+            (func $add (export "add") (param i32 i32) (result i32)
+                local.get 0
+                local.get 1
+                i32.add))"#,
+    )
+    .unwrap();
+    append_custom_section(&mut module, "producers", b"\0");
+    fs::write(&input, &module).unwrap();
+
+    let optimize = |size: &str| {
+        let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+        cmd.current_dir(temp_dir.path())
+            .env("WASM_WIZARD_CACHE_DIR", cache_dir.path())
+            .env("PATH", "")
+            .arg("optimize")
+            .arg(&input)
+            .arg("--output")
+            .arg(&output)
+            .arg("--size")
+            .arg(size)
+            .arg("--no-cache");
+        cmd.assert()
+    };
+
+    optimize("1mb")
+        .success()
+        .stdout(predicate::str::contains(
+            "level 1: dead code elimination reached the target",
+        ))
+        .stdout(predicate::str::contains("Trying level 2").not());
+    assert!(fs::read(&output).unwrap().len() < module.len());
+
+    // No strategy gets below a handful of bytes; the closest output is kept
+    optimize("10")
+        .failure()
+        .stdout(predicate::str::contains(
+            "Trying level 2, strip names and producers",
+        ))
+        .stdout(predicate::str::contains("Largest Remaining Contributors"))
+        .stdout(predicate::str::contains("func[0]"))
+        .stdout(predicate::str::contains("the search is limited"))
+        .stderr(predicate::str::contains(
+            "Could not reach the target size of 10 bytes",
+        ))
+        .stderr(predicate::str::contains("wasm-opt, which was not found"));
+    let closest = fs::read(&output).unwrap();
+    wasmparser::Validator::new().validate_all(&closest).unwrap();
+    let (_, custom) = wasm_sections(&closest);
+    assert!(custom.is_empty(), "{custom:?}");
+}
+
+#[test]
+fn test_wasm_opt_gets_only_detected_features() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    let bin = temp_dir.path().join("bin");
+    let args_file = temp_dir.path().join("wasm-opt-args");
+    fs::create_dir(&bin).unwrap();
+
+    // Records its arguments and copies the input to the output
+    let wasm_opt = bin.join("wasm-opt");
+    fs::write(
+        &wasm_opt,
+        format!(
+            "#!/bin/sh\necho \"$@\" > {}\nwhile [ $# -gt 0 ]; do\n  case \"$1\" in\n    -o) shift; out=\"$1\" ;;\n    *.wasm) in=\"$1\" ;;\n  esac\n  shift\ndone\ncp \"$in\" \"$out\"\n",
+            args_file.display()
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&wasm_opt, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let input = temp_dir.path().join("module.wasm");
+    let module = wat::parse_str(
+        r#"(module
+            (func (export "widen") (param i32) (result i32) (i32.extend8_s (local.get 0)))
+            (func (export "splat") (param i32) (result v128) (i32x4.splat (local.get 0))))"#,
+    )
+    .unwrap();
+    fs::write(&input, module).unwrap();
+
+    let optimize = |optimize_section: &str| {
+        fs::write(
+            temp_dir.path().join("wasm-wizard.toml"),
+            format!("[project]\nname = \"features\"\n\n[optimize]\n{optimize_section}"),
+        )
+        .unwrap();
+        let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+        cmd.current_dir(temp_dir.path())
+            .env("WASM_WIZARD_CACHE_DIR", cache_dir.path())
+            .env("PATH", format!("{}:/usr/bin:/bin", bin.display()))
+            .arg("optimize")
+            .arg(&input)
+            .arg("--no-cache");
+        cmd.assert()
+    };
+
+    optimize("enable_features = [\"bulk-memory\"]\n")
+        .success()
+        .stdout(predicate::str::contains("Features in use: sign-ext, simd"));
+    let args = fs::read_to_string(&args_file).unwrap();
+    for flag in [
+        "--mvp-features",
+        "--enable-sign-ext",
+        "--enable-simd",
+        "--enable-bulk-memory",
+    ] {
+        assert!(args.contains(flag), "{flag} missing from {args}");
+    }
+    assert!(!args.contains("--enable-threads"), "{args}");
+
+    optimize("disable_features = [\"simd\"]\n")
+        .failure()
+        .stderr(predicate::str::contains(
+            "The input uses the simd feature, which `optimize.disable_features` forbids",
+        ));
+}
+
+#[test]
+fn test_optimize_component_optimizes_each_core_module() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache_dir = TempDir::new().unwrap();
+    let input = temp_dir.path().join("app.wasm");
+    let output = temp_dir.path().join("app.optimized.wasm");
+
+    let component = wat::parse_str(
+        r#"(component
+            (core module $main
+                (func $unused (result i32) i32.const 1)
+                (func (export "run") (result i32) i32.const 7))
+            (core module $helper
+                (@custom ".debug_info" "dwarf")
+                (func (export "help")))
+            (core instance $main (instantiate $main))
+            (core instance (instantiate $helper))
+            (func (export "run") (result u32)
+                (canon lift (core func $main "run"))))"#,
+    )
+    .unwrap();
+    fs::write(&input, &component).unwrap();
+
+    let mut cmd = Command::cargo_bin("wasm-wizard").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .env("WASM_WIZARD_CACHE_DIR", cache_dir.path())
+        .env("PATH", "")
+        .arg("optimize")
+        .arg(&input)
+        .arg("--output")
+        .arg(&output)
+        .arg("--no-cache");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Core Modules:"))
+        .stdout(predicate::str::contains("#1"))
+        .stdout(predicate::str::contains(
+            "Optimized 2 core module(s) of the component",
+        ))
+        .stdout(predicate::function(|stdout: &str| {
+            stdout.matches("wasm-opt not found").count() == 1
+        }));
+
+    let optimized = fs::read(&output).unwrap();
+    assert!(optimized.len() < component.len());
+    wasmparser::Validator::new_with_features(
+        wasmparser::WasmFeatures::default() | wasmparser::WasmFeatures::COMPONENT_MODEL,
+    )
+    .validate_all(&optimized)
+    .unwrap();
+
+    let mut functions = Vec::new();
+    let mut exports = Vec::new();
+    for payload in wasmparser::Parser::new(0).parse_all(&optimized) {
+        match payload.unwrap() {
+            wasmparser::Payload::FunctionSection(reader) => functions.push(reader.count()),
+            wasmparser::Payload::ComponentExportSection(reader) => {
+                for export in reader {
+                    exports.push(export.unwrap().name.0.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    assert_eq!(functions, [1, 1]);
+    assert_eq!(exports, ["run"]);
+}